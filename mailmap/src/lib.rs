@@ -1,7 +1,6 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::hash::Hash;
-use std::pin::Pin;
-use std::ptr::NonNull;
 
 use unicase::UniCase;
 
@@ -10,49 +9,100 @@ mod test;
 
 /// Loads a mailmap from the string passed in.
 ///
-/// The format is the same as used by `git`; specifically:
+/// The format and semantics are the same as `git check-mailmap`'s; specifically:
 ///
 /// * `Canonical Name <canonical email> Current Name <current email>`
 ///   * This changes authors matching both name and email to the canonical forms.
 /// * `Canonical Name <current email>`
-///   * This changes all entries with this email to new name, regardless of their current name.
+///   * This changes the name of all entries with this email, regardless of their
+///     current name, and leaves the email as is.
 /// * `Canonical Name <canonical email> <current email>`
 ///   * This changes all entries with the current email to the canonical name and email.
 /// * `<canonical email> <current email>`
 ///   * This changes all entries with the current email to the canonical email.
+///
+/// Names and emails are compared ignoring ASCII case. An entry matching both
+/// name and email takes precedence over entries matching only the email. When
+/// several entries of the same kind match, later ones override earlier ones;
+/// for email-only entries this happens field by field, so a line setting only
+/// the name and a later one setting only the email both apply.
+///
+/// Lines starting with `#` are comments, as is anything after the second email
+/// on a line. Lines without a non-empty first email are ignored. Git ignores
+/// these silently; [`Mailmap::from_string`] instead fails on them, while
+/// [`Mailmap::from_string_lenient`] reports them and carries on like git.
+///
+/// A mailmap can also be built up with [`Mailmap::add`], and is written back
+/// out in the same format by its `Display` implementation, one entry per line.
+/// Comments and blank lines aren't kept.
+#[derive(Clone, Default)]
 pub struct Mailmap {
-    buffer: Pin<Box<str>>,
-    entries: Vec<RawMapEntry>,
+    entries: Vec<Entry>,
+    /// The entries applying to each ASCII-lowercased current email.
+    index: HashMap<String, EmailIndex>,
+}
+
+/// Indices into `Mailmap::entries` of the entries matching one email, keeping
+/// only those which take precedence.
+#[derive(Clone, Default)]
+struct EmailIndex {
+    /// The last email-only entry setting a name.
+    simple_name: Option<usize>,
+    /// The last email-only entry setting an email.
+    simple_email: Option<usize>,
+    /// The last entry for each ASCII-lowercased current name.
+    names: HashMap<String, usize>,
 }
 
 impl fmt::Debug for Mailmap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut list = f.debug_list();
+        f.debug_list().entries(&self.entries).finish()
+    }
+}
+
+impl fmt::Display for Mailmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for entry in &self.entries {
-            // these entries were created from this buffer
-            let entry = unsafe { entry.to_entry(&self.buffer) };
-            list.entry(&entry);
+            writeln!(f, "{}", entry)?;
         }
-        list.finish()
+        Ok(())
     }
 }
 
-#[derive(Copy, Clone)]
-struct RawMapEntry {
-    canonical_name: Option<NonNull<str>>,
-    canonical_email: Option<NonNull<str>>,
-    current_name: Option<NonNull<str>>,
-    current_email: Option<NonNull<str>>,
+/// A single mapping of a mailmap, i.e. one line of a `.mailmap` file.
+///
+/// Authors with `current_email` (and `current_name`, if set) are mapped to the
+/// canonical name and email, keeping their own for whichever isn't set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub canonical_name: Option<String>,
+    pub canonical_email: Option<String>,
+    /// Only set for entries matching on both name and email.
+    pub current_name: Option<String>,
+    pub current_email: String,
 }
 
-impl RawMapEntry {
-    unsafe fn to_entry<'a>(self, _: &'a str) -> MapEntry<'a> {
-        MapEntry {
-            canonical_name: self.canonical_name.map(|v| &*v.as_ptr()),
-            canonical_email: self.canonical_email.map(|v| &*v.as_ptr()),
-            current_name: self.current_name.map(|v| &*v.as_ptr()),
-            current_email: self.current_email.map(|v| &*v.as_ptr()),
+impl fmt::Display for Entry {
+    /// Write the entry as a mailmap line.
+    ///
+    /// The format has no way to match on a name without also replacing the
+    /// email, so entries with a `current_name` but no `canonical_email` are
+    /// written with the current email as the canonical one. Names and emails
+    /// containing `<`, `>` or line breaks can't be written back faithfully.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.canonical_name {
+            write!(f, "{} ", name)?;
         }
+        let canonical_email = match (&self.canonical_email, &self.current_name) {
+            (Some(email), _) => email,
+            (None, Some(_)) => &self.current_email,
+            (None, None) => return write!(f, "<{}>", self.current_email),
+        };
+        write!(f, "<{}> ", canonical_email)?;
+        if let Some(name) = &self.current_name {
+            write!(f, "{} ", name)?;
+        }
+        write!(f, "<{}>", self.current_email)
     }
 }
 
@@ -61,20 +111,71 @@ struct MapEntry<'a> {
     canonical_name: Option<&'a str>,
     canonical_email: Option<&'a str>,
     current_name: Option<&'a str>,
-    current_email: Option<&'a str>,
+    current_email: &'a str,
 }
 
-impl<'a> MapEntry<'a> {
-    fn to_raw_entry(self) -> RawMapEntry {
-        RawMapEntry {
-            canonical_name: self.canonical_name.map(|v| v.into()),
-            canonical_email: self.canonical_email.map(|v| v.into()),
-            current_name: self.current_name.map(|v| v.into()),
-            current_email: self.current_email.map(|v| v.into()),
+impl From<MapEntry<'_>> for Entry {
+    fn from(entry: MapEntry<'_>) -> Self {
+        Entry {
+            canonical_name: entry.canonical_name.map(String::from),
+            canonical_email: entry.canonical_email.map(String::from),
+            current_name: entry.current_name.map(String::from),
+            current_email: entry.current_email.into(),
         }
     }
 }
 
+/// A part of a mailmap which git ignores, which is usually a mistake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line it's on, starting at 1.
+    pub line: usize,
+    /// The character it starts at within the line, starting at 1.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A line which isn't blank or a comment has no email, and is ignored.
+    MissingEmail,
+    /// An email has no closing `>`. If it's the first on the line, the line is
+    /// ignored; otherwise only the first email is read.
+    UnterminatedEmail,
+    /// The first email on a line is `<>`, so the line is ignored.
+    EmptyEmail,
+    /// Text after the last part of the entry on a line is ignored.
+    TrailingText,
+    /// A line starting with whitespace and then `#` isn't a comment, and is
+    /// read as an entry if it contains an email.
+    IndentedComment,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ParseErrorKind::MissingEmail => "line has no email and is ignored",
+            ParseErrorKind::UnterminatedEmail => "email is missing its closing `>`",
+            ParseErrorKind::EmptyEmail => "first email is empty, so the line is ignored",
+            ParseErrorKind::TrailingText => "trailing text is ignored",
+            ParseErrorKind::IndentedComment => "indented comment is read as an entry",
+        };
+        f.write_str(reason)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
 pub struct Author {
     pub name: UniCase<String>,
@@ -97,129 +198,202 @@ impl fmt::Debug for Author {
 }
 
 impl Mailmap {
-    pub fn from_string(file: String) -> Result<Mailmap, Box<dyn std::error::Error>> {
-        let file = Pin::new(file.into_boxed_str());
-        let mut entries = Vec::with_capacity(file.lines().count());
-        for (idx, line) in file.lines().enumerate() {
-            if let Some(entry) = parse_line(line, idx + 1) {
-                entries.push(entry.to_raw_entry());
+    /// Create a mailmap without any entries.
+    pub fn new() -> Self {
+        Mailmap::default()
+    }
+
+    /// Parse a mailmap, failing on the first part of it which git would ignore
+    /// (see [`ParseErrorKind`]).
+    pub fn from_string(file: String) -> Result<Mailmap, ParseError> {
+        let (mailmap, errors) = Mailmap::from_string_lenient(&file);
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(mailmap),
+        }
+    }
+
+    /// Parse a mailmap exactly like git does, also returning every part of it
+    /// which git ignores, in order.
+    ///
+    /// Lines git ignores entirely are skipped, and the rest of the entries are
+    /// read the same as with [`Mailmap::from_string`].
+    pub fn from_string_lenient(file: &str) -> (Mailmap, Vec<ParseError>) {
+        let mut mailmap = Mailmap::new();
+        let mut errors = Vec::new();
+        for (idx, line) in file.split('\n').enumerate() {
+            if let Some((offset, kind)) = check_line(line) {
+                errors.push(ParseError {
+                    line: idx + 1,
+                    column: line[..offset].chars().count() + 1,
+                    kind,
+                });
+            }
+            if let Some(entry) = parse_line(line) {
+                mailmap.add(entry.into());
             }
         }
-        Ok(Mailmap {
-            buffer: file,
-            entries,
-        })
+        (mailmap, errors)
     }
 
-    pub fn canonicalize(&self, author: &Author) -> Author {
-        for entry in &self.entries {
-            // these entries were created from this buffer
-            let entry = unsafe { entry.to_entry(&self.buffer) };
-            if let Some(email) = entry.current_email {
-                if let Some(name) = entry.current_name {
-                    if author.name == UniCase::new(name) && author.email == UniCase::new(email) {
-                        return Author::new(
-                            entry.canonical_name.unwrap_or(&author.name).to_owned(),
-                            entry.canonical_email.expect("canonical email").to_owned(),
-                        );
-                    }
-                } else {
-                    if author.email == UniCase::new(email) {
-                        return Author::new(
-                            entry.canonical_name.unwrap_or(&author.name).to_owned(),
-                            entry.canonical_email.expect("canonical email").to_owned(),
-                        );
-                    }
+    /// The entries of the mailmap, in the order they were added.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    /// Add an entry after all existing ones, so that it overrides any of them
+    /// matching the same authors.
+    pub fn add(&mut self, entry: Entry) {
+        let idx = self.entries.len();
+        let email = self
+            .index
+            .entry(entry.current_email.to_ascii_lowercase())
+            .or_default();
+        // Later entries override earlier ones, field by field for email-only ones.
+        match &entry.current_name {
+            Some(name) => {
+                email.names.insert(name.to_ascii_lowercase(), idx);
+            }
+            None => {
+                if entry.canonical_name.is_some() {
+                    email.simple_name = Some(idx);
+                }
+                if entry.canonical_email.is_some() {
+                    email.simple_email = Some(idx);
                 }
             }
         }
-
-        author.clone()
+        self.entries.push(entry);
     }
-}
 
-fn read_email<'a>(line: &mut &'a str) -> Option<&'a str> {
-    if !line.starts_with('<') {
-        return None;
+    pub fn canonicalize(&self, author: &Author) -> Author {
+        let Some(index) = self.index.get(&author.email.to_ascii_lowercase()) else {
+            return author.clone();
+        };
+
+        // An entry matching the name as well takes precedence over email-only ones.
+        let complex = if index.names.is_empty() {
+            None
+        } else {
+            index.names.get(&author.name.to_ascii_lowercase())
+        };
+        let (name, email) = match complex {
+            Some(&idx) => {
+                let entry = &self.entries[idx];
+                (&entry.canonical_name, &entry.canonical_email)
+            }
+            None => (
+                index
+                    .simple_name
+                    .map_or(&None, |idx| &self.entries[idx].canonical_name),
+                index
+                    .simple_email
+                    .map_or(&None, |idx| &self.entries[idx].canonical_email),
+            ),
+        };
+        Author::new(
+            name.as_deref().unwrap_or(&author.name).to_owned(),
+            email.as_deref().unwrap_or(&author.email).to_owned(),
+        )
     }
+}
 
-    let end = line
-        .find('>')
-        .unwrap_or_else(|| panic!("could not find email end in {:?}", line));
-    let ret = &line[1..end];
-    *line = &line[end + 1..];
-    Some(ret)
+/// Whether `c` is whitespace according to C's `isspace`, which git trims names with.
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0b' | '\x0c' | '\r')
 }
 
-fn read_name<'a>(line: &mut &'a str) -> Option<&'a str> {
-    let end = line.find('<')?;
-    let ret = &line[..end].trim();
-    *line = &line[end..];
-    if ret.is_empty() {
-        None
-    } else {
-        Some(ret)
+/// Parse a name followed by an email in angle brackets, the same way as git's
+/// `parse_name_and_email`.
+///
+/// Returns the name, if it isn't empty, the email, and the rest of the line, if
+/// it isn't empty. Returns `None` if there's no email, or if it's empty and
+/// `allow_empty_email` isn't set.
+fn parse_name_and_email(
+    buffer: &str,
+    allow_empty_email: bool,
+) -> Option<(Option<&str>, &str, Option<&str>)> {
+    let left = buffer.find('<')?;
+    let right = left + 1 + buffer[left + 1..].find('>')?;
+    if !allow_empty_email && left + 1 == right {
+        return None;
     }
+    let name = buffer[..left].trim_matches(is_space);
+    let rest = &buffer[right + 1..];
+    Some((
+        Some(name).filter(|n| !n.is_empty()),
+        &buffer[left + 1..right],
+        Some(rest).filter(|r| !r.is_empty()),
+    ))
 }
 
-fn read_comment(line: &mut &str) -> bool {
-    if line.trim().starts_with('#') {
-        *line = "";
-        true
-    } else {
-        false
+/// Parse a line of a mailmap the same way as git's `read_mailmap_line`.
+fn parse_line(line: &str) -> Option<MapEntry<'_>> {
+    if line.starts_with('#') {
+        return None;
     }
+    let (name, email, rest) = parse_name_and_email(line, false)?;
+    let entry = match rest.and_then(|rest| parse_name_and_email(rest, true)) {
+        Some((current_name, current_email, _)) => MapEntry {
+            canonical_name: name,
+            canonical_email: Some(email),
+            current_name,
+            current_email,
+        },
+        // With a single email, only the name is replaced.
+        None => MapEntry {
+            canonical_name: name,
+            canonical_email: None,
+            current_name: None,
+            current_email: email,
+        },
+    };
+    Some(entry)
 }
 
-fn parse_line(mut line: &str, idx: usize) -> Option<MapEntry<'_>> {
-    let mut entry = MapEntry {
-        canonical_name: None,
-        canonical_email: None,
-        current_name: None,
-        current_email: None,
-    };
-    loop {
-        line = line.trim_start();
-        if read_comment(&mut line) || line.trim().is_empty() {
-            break;
-        }
+/// Find the first part of a line which git ignores, other than comments and
+/// blank lines, returning its byte offset and why it's ignored.
+fn check_line(line: &str) -> Option<(usize, ParseErrorKind)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let start = line.len() - line.trim_start_matches(is_space).len();
+    if start == line.len() {
+        return None;
+    }
+    if line[start..].starts_with('#') {
+        // Harmless, unless git finds an entry in it.
+        return parse_name_and_email(line, false).map(|_| (start, ParseErrorKind::IndentedComment));
+    }
 
-        if let Some(email) = read_email(&mut line) {
-            if entry.canonical_email.is_none() {
-                entry.canonical_email = Some(email);
-            } else {
-                if entry.current_email.is_some() {
-                    eprintln!("malformed mailmap on line {}: too many emails", idx);
-                } else {
-                    entry.current_email = Some(email);
-                }
-            }
-        } else if let Some(name) = read_name(&mut line) {
-            if entry.canonical_name.is_none() {
-                entry.canonical_name = Some(name);
-            } else {
-                if entry.current_name.is_some() {
-                    eprintln!("malformed mailmap on line {}: too many names", idx);
-                } else {
-                    entry.current_name = Some(name);
+    let rest = match parse_name_and_email(line, false) {
+        Some((_, _, rest)) => rest?,
+        None => {
+            let kind = match line.find('<') {
+                None => return Some((start, ParseErrorKind::MissingEmail)),
+                Some(left) if !line[left..].contains('>') => {
+                    (left, ParseErrorKind::UnterminatedEmail)
                 }
-            }
-        } else {
-            break;
+                Some(left) => (left, ParseErrorKind::EmptyEmail),
+            };
+            return Some(kind);
         }
+    };
+    // `rest` and anything after it are suffixes of `line`.
+    let offset = |suffix: &str| line.len() - suffix.len();
+    match parse_name_and_email(rest, true) {
+        Some((_, _, trailing)) => trailing_text(line, offset(trailing?)),
+        None => match rest.find('<') {
+            Some(left) => Some((offset(rest) + left, ParseErrorKind::UnterminatedEmail)),
+            None => trailing_text(line, offset(rest)),
+        },
     }
+}
 
-    if entry.canonical_email.is_some() && entry.current_email.is_none() {
-        entry.current_email = entry.canonical_email;
-    }
-
-    if entry.canonical_name.is_some()
-        || entry.canonical_email.is_some()
-        || entry.current_name.is_some()
-        || entry.current_email.is_some()
-    {
-        Some(entry)
-    } else {
-        None
-    }
+/// The offset of the text following `offset` in `line`, if there's any besides
+/// whitespace and a `#` comment.
+fn trailing_text(line: &str, offset: usize) -> Option<(usize, ParseErrorKind)> {
+    let text = line[offset..].trim_start_matches(is_space);
+    let ignored = text.is_empty() || text.starts_with('#');
+    (!ignored).then(|| (line.len() - text.len(), ParseErrorKind::TrailingText))
 }
@@ -1,77 +1,49 @@
 use super::*;
 
-macro_rules! test_parser {
-    ($parser:ident, $input:expr, $exp:expr $(,)*) => {{
-        let mut input = $input;
-        let ret = $parser(&mut input);
-        assert_eq!(input, $exp);
-        ret
-    }};
-}
-
 #[test]
-fn comment_1() {
-    test_parser!(read_comment, "# foo", "");
-}
-
-#[test]
-fn comment_2() {
-    test_parser!(read_comment, "bar # foo", "bar # foo");
-}
-
-#[test]
-fn email_1() {
+fn name_and_email_1() {
     assert_eq!(
-        test_parser!(read_email, "<foo@example.com>", ""),
-        Some("foo@example.com")
+        parse_name_and_email("Canonical Name <foo@example.com>", false),
+        Some((Some("Canonical Name"), "foo@example.com", None))
     );
 }
 
 #[test]
-fn email_2() {
+fn name_and_email_2() {
     assert_eq!(
-        test_parser!(
-            read_email,
-            "<foo@example.com> <foo2@example.com>",
-            " <foo2@example.com>"
-        ),
-        Some("foo@example.com")
+        parse_name_and_email("<foo@example.com> <foo2@example.com>", false),
+        Some((None, "foo@example.com", Some(" <foo2@example.com>")))
     );
 }
 
 #[test]
-fn email_3() {
+fn name_and_email_3() {
     assert_eq!(
-        test_parser!(
-            read_email,
-            "Bar <foo@example.com> <foo2@example.com>",
-            "Bar <foo@example.com> <foo2@example.com>",
-        ),
-        None
+        parse_name_and_email(" \t Spaced  Name \x0b<foo@example.com>", false),
+        Some((Some("Spaced  Name"), "foo@example.com", None))
     );
 }
 
 #[test]
-fn name_1() {
+fn name_and_email_4() {
+    assert_eq!(parse_name_and_email("Name <>", false), None);
     assert_eq!(
-        test_parser!(
-            read_name,
-            "Canonical Name <foo@example.com>",
-            "<foo@example.com>"
-        ),
-        Some("Canonical Name"),
+        parse_name_and_email("Name <>", true),
+        Some((Some("Name"), "", None))
     );
+    assert_eq!(parse_name_and_email("Name <foo@example.com", false), None);
+    assert_eq!(parse_name_and_email("Name", false), None);
 }
 
 #[test]
 fn line_1() {
     assert_eq!(
-        parse_line("Joe Bob <email1> <email2>", 0),
+        parse_line("Joe Bob <email1> <email2>"),
         Some(MapEntry {
             canonical_name: Some("Joe Bob"),
             canonical_email: Some("email1"),
             current_name: None,
-            current_email: Some("email2"),
+            current_email: "email2",
         })
     );
 }
@@ -79,12 +51,40 @@ fn line_1() {
 #[test]
 fn line_2() {
     assert_eq!(
-        parse_line("Joe Bob <email1>", 0),
+        parse_line("Joe Bob <email1>"),
         Some(MapEntry {
             canonical_name: Some("Joe Bob"),
+            canonical_email: None,
+            current_name: None,
+            current_email: "email1",
+        })
+    );
+}
+
+#[test]
+fn line_3() {
+    assert_eq!(
+        parse_line("<email1> Joe <email2> trailing <email3>"),
+        Some(MapEntry {
+            canonical_name: None,
             canonical_email: Some("email1"),
+            current_name: Some("Joe"),
+            current_email: "email2",
+        })
+    );
+}
+
+#[test]
+fn comments() {
+    assert_eq!(parse_line("# Joe Bob <email1>"), None);
+    // Only lines starting with `#` are comments.
+    assert_eq!(
+        parse_line(" # Joe Bob <email1>"),
+        Some(MapEntry {
+            canonical_name: Some("# Joe Bob"),
+            canonical_email: None,
             current_name: None,
-            current_email: Some("email1"),
+            current_email: "email1",
         })
     );
 }
@@ -97,7 +97,7 @@ fn a(name: &str, email: &str) -> Author {
 }
 
 fn map(line: &str) -> Mailmap {
-    Mailmap::from_string(line.into()).unwrap()
+    Mailmap::from_string_lenient(line).0
 }
 
 #[test]
@@ -123,3 +123,281 @@ fn map_4() {
     let mm = map("<PE> <CE>");
     assert_eq!(mm.canonicalize(&a("any", "CE")), a("any", "PE"));
 }
+
+/// Check that `mailmap` maps each of `cases` the same way `git check-mailmap` does.
+///
+/// The expected results were produced with git 2.39.
+fn conformance(mailmap: &str, cases: &[(&str, &str, &str, &str)]) {
+    let mm = map(mailmap);
+    for &(name, email, exp_name, exp_email) in cases {
+        let got = mm.canonicalize(&a(name, email));
+        // Compare exactly, as `Author`'s equality ignores case.
+        assert_eq!(
+            (got.name.as_str(), got.email.as_str()),
+            (exp_name, exp_email),
+            "mapping {} <{}> with {:?}",
+            name,
+            email,
+            mailmap
+        );
+    }
+}
+
+#[test]
+fn git_docs_example_1() {
+    conformance(
+        "Joe Developer <joe@example.com>\n\
+         Jane Doe <jane@example.com>\n\
+         Jane Doe <jane@desktop.(none)>\n",
+        &[
+            ("joe", "joe@example.com", "Joe Developer", "joe@example.com"),
+            (
+                "Jane",
+                "jane@desktop.(none)",
+                "Jane Doe",
+                "jane@desktop.(none)",
+            ),
+            // Only the name is replaced, so the email keeps its case.
+            ("JANE", "JANE@EXAMPLE.COM", "Jane Doe", "JANE@EXAMPLE.COM"),
+        ],
+    );
+}
+
+#[test]
+fn git_docs_example_2() {
+    conformance(
+        "<cto@company.xx>                       <cto@coompany.xx>\n\
+         Some Dude <some@dude.xx>         nick1 <bugs@company.xx>\n\
+         Other Author <other@author.xx>   nick2 <bugs@company.xx>\n\
+         Other Author <other@author.xx>         <nick2@company.xx>\n\
+         Santa Claus <santa.claus@northpole.xx> <me@company.xx>\n",
+        &[
+            ("CTO", "cto@coompany.xx", "CTO", "cto@company.xx"),
+            ("nick1", "bugs@company.xx", "Some Dude", "some@dude.xx"),
+            (
+                "NICK2",
+                "bugs@company.xx",
+                "Other Author",
+                "other@author.xx",
+            ),
+            ("nick3", "bugs@company.xx", "nick3", "bugs@company.xx"),
+            (
+                "Whoever",
+                "nick2@company.xx",
+                "Other Author",
+                "other@author.xx",
+            ),
+            (
+                "Me",
+                "me@company.xx",
+                "Santa Claus",
+                "santa.claus@northpole.xx",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn later_entries_override_earlier() {
+    conformance("A <a@x>\nB <a@x>\n", &[("x", "a@x", "B", "a@x")]);
+    conformance(
+        "First <f@x> old <a@x>\nSecond <s@x> old <a@x>\n",
+        &[("old", "a@x", "Second", "s@x")],
+    );
+}
+
+#[test]
+fn simple_entries_merge() {
+    conformance(
+        "Name <a@x>\n<new@x> <a@x>\n",
+        &[("old", "a@x", "Name", "new@x")],
+    );
+}
+
+#[test]
+fn complex_entries_take_precedence() {
+    conformance(
+        "Complex <c@x> old <a@x>\nSimple <s@x> <a@x>\n",
+        &[
+            ("old", "a@x", "Complex", "c@x"),
+            ("OLD", "A@X", "Complex", "c@x"),
+            ("other", "a@x", "Simple", "s@x"),
+        ],
+    );
+    conformance("C <c@x> old <a@x>\n", &[("new", "a@x", "new", "a@x")]);
+}
+
+#[test]
+fn odd_lines() {
+    conformance(
+        "# Foo <a@x>\n # Bar <b@x>\n",
+        &[("x", "a@x", "x", "a@x"), ("x", "b@x", "# Bar", "b@x")],
+    );
+    conformance(
+        "New <n@x> <a@x> trailing\nOther <o@x> <b@x> Third <t@x>\nJunk <j@x> junk\n",
+        &[
+            ("x", "a@x", "New", "n@x"),
+            ("Third", "t@x", "Third", "t@x"),
+            ("x", "b@x", "Other", "o@x"),
+            ("x", "j@x", "Junk", "j@x"),
+        ],
+    );
+    conformance(
+        "  Spaced   Name   <a@x>\nUnterminated <b@x\nTab\tName\t<t@x>\t<u@x>\n",
+        &[
+            ("x", "a@x", "Spaced   Name", "a@x"),
+            ("x", "b@x", "x", "b@x"),
+            ("x", "u@x", "Tab\tName", "t@x"),
+        ],
+    );
+}
+
+#[test]
+fn empty_emails() {
+    conformance(
+        "Name <>\nNew <n@x> Old <>\n",
+        &[("x", "", "x", ""), ("Old", "", "New", "n@x")],
+    );
+    conformance("<>\n<n@x> <>\n", &[("x", "", "x", "n@x")]);
+}
+
+#[test]
+fn case_is_ignored_for_ascii_only() {
+    conformance("Émile <e@x>\n", &[("x", "E@X", "Émile", "E@X")]);
+    conformance(
+        "Émile <e@x> émile <f@x>\n",
+        &[
+            ("ÉMILE", "f@x", "ÉMILE", "f@x"),
+            ("émile", "F@X", "Émile", "e@x"),
+        ],
+    );
+}
+
+#[test]
+fn interleaved_entries() {
+    conformance(
+        "A1 <a1@x> <a@x>\n\
+         B1 <b1@x> old <b@x>\n\
+         <a2@x> <A@X>\n\
+         B2 <b2@x> OLD <B@X>\n\
+         A3 <a@x>\n\
+         Other <o@x> other <b@x>\n",
+        &[
+            ("x", "a@x", "A3", "a2@x"),
+            ("old", "b@x", "B2", "b2@x"),
+            ("Other", "b@x", "Other", "o@x"),
+            ("x", "b@x", "x", "b@x"),
+            ("x", "c@x", "x", "c@x"),
+        ],
+    );
+}
+
+fn entry(
+    canonical_name: Option<&str>,
+    canonical_email: Option<&str>,
+    current_name: Option<&str>,
+    current_email: &str,
+) -> Entry {
+    Entry {
+        canonical_name: canonical_name.map(String::from),
+        canonical_email: canonical_email.map(String::from),
+        current_name: current_name.map(String::from),
+        current_email: current_email.into(),
+    }
+}
+
+#[test]
+fn add_entries() {
+    let mut mm = Mailmap::new();
+    mm.add(entry(Some("PN"), Some("PE"), None, "CE"));
+    assert_eq!(mm.canonicalize(&a("any", "CE")), a("PN", "PE"));
+    // Later entries override earlier ones, as in a file.
+    mm.add(entry(Some("Other"), None, None, "ce"));
+    assert_eq!(mm.canonicalize(&a("any", "CE")), a("Other", "PE"));
+    assert_eq!(mm.entries().count(), 2);
+}
+
+#[test]
+fn display_round_trip() {
+    let text = "# comment\n\
+                PN <PE> CN <CE>\n\
+                \n\
+                PN <CE2>\n\
+                PN <PE> <CE3>\n\
+                <PE> <CE4>\n\
+                <PE> CN <CE5>\n\
+                New <n@x> Old <>\n";
+    let mm = map(text);
+    let written = mm.to_string();
+    assert_eq!(
+        written,
+        "PN <PE> CN <CE>\n\
+         PN <CE2>\n\
+         PN <PE> <CE3>\n\
+         <PE> <CE4>\n\
+         <PE> CN <CE5>\n\
+         New <n@x> Old <>\n"
+    );
+    let reread = map(&written);
+    assert_eq!(
+        reread.entries().collect::<Vec<_>>(),
+        mm.entries().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn display_name_only_match() {
+    // There's no syntax for matching on a name without a canonical email.
+    let e = entry(Some("PN"), None, Some("CN"), "CE");
+    assert_eq!(e.to_string(), "PN <CE> CN <CE>");
+}
+
+fn errors(file: &str) -> Vec<(usize, usize, ParseErrorKind)> {
+    Mailmap::from_string_lenient(file)
+        .1
+        .into_iter()
+        .map(|e| (e.line, e.column, e.kind))
+        .collect()
+}
+
+#[test]
+fn parse_errors() {
+    use ParseErrorKind::*;
+    assert_eq!(
+        errors(
+            "# comment\n\
+             \x20 # indented comment\n\
+             \x20 # indented <a@x>\n\
+             Name only\n\
+             Name <unterminated\n\
+             Name <>\n\
+             Name <a@x> trailing\n\
+             Name <a@x> Other <b@x> trailing\n\
+             Name <a@x> Other <b@x\n\
+             Émile <e@x> <f@x> # too many <g@x>\n\
+             Name <a@x> # comment\n\
+             \t\n\
+             Fine <a@x> <b@x>\r\n"
+        ),
+        [
+            (3, 3, IndentedComment),
+            (4, 1, MissingEmail),
+            (5, 6, UnterminatedEmail),
+            (6, 6, EmptyEmail),
+            (7, 12, TrailingText),
+            (8, 24, TrailingText),
+            (9, 18, UnterminatedEmail),
+        ]
+    );
+}
+
+#[test]
+fn strict_parsing() {
+    assert!(Mailmap::from_string("Name <a@x>\n# comment\n\n".into()).is_ok());
+    assert!(Mailmap::from_string("Name <a@x> <b@x> # comment\n".into()).is_ok());
+    let error = Mailmap::from_string("Name <a@x>\nName <b@x\nName <>\n".into()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 2, column 6: email is missing its closing `>`"
+    );
+}
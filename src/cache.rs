@@ -0,0 +1,299 @@
+//! On-disk cache of the contributors of each finished release.
+//!
+//! Computing the contributors of a release means walking the whole history of
+//! the repository and its submodules up to it, and up to the release before it.
+//! Released versions never change, so their [`AuthorMap`]s are stored here and
+//! later runs only walk the history for the versions still in progress. The
+//! maps are stored before the mailmap is applied, so that editing it doesn't
+//! invalidate them.
+//!
+//! Entries are keyed by a fingerprint of everything that affects the result
+//! (see [`fingerprint`]) along with the commits of the release and of the
+//! release before it. Any entry not used by a run is removed at its end, so
+//! entries made stale by reviewer changes don't pile up.
+
+use crate::hash::StableHasher;
+use crate::reviewers::Reviewers;
+use crate::settings::{BotAction, BotSettings, CacheSettings, ProjectSettings, TrailerSettings};
+use crate::{AuthorMap, Kinds, Occurrences};
+use git2::Oid;
+use mailmap::Author;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Bumped whenever the format of the cache files, or the way the cached data
+/// is computed, changes.
+const CACHE_FORMAT: u32 = 1;
+
+pub struct Cache {
+    directory: PathBuf,
+    /// Entries read or written during this run.
+    used: Mutex<HashSet<PathBuf>>,
+}
+
+/// Hash everything that affects the contributors computed for a project,
+/// other than the commits of the releases themselves.
+pub fn fingerprint(
+    project: &ProjectSettings,
+    reviewers: &Reviewers,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(&CACHE_FORMAT.to_le_bytes());
+    hasher.write_str(&project.repository);
+    for include in &project.submodules.include {
+        hasher.write_str(include);
+    }
+    hasher.write(&[0]);
+    for exclude in &project.submodules.exclude {
+        hasher.write_str(exclude);
+    }
+    hasher.write(&[0]);
+    for extractor in &project.reviewer_extractors {
+        hasher.write(&[*extractor as u8]);
+    }
+    hasher.write(&[0xff]);
+    hasher.write(&reviewers.fingerprint().to_le_bytes());
+    hasher.write(&[(bots.action == BotAction::Report) as u8]);
+    for patterns in [&bots.names, &bots.emails, &bots.domains] {
+        for pattern in patterns {
+            hasher.write_str(pattern);
+        }
+        hasher.write(&[0]);
+    }
+    for (key, kind) in trailers.iter() {
+        hasher.write_str(key);
+        hasher.write(&[kind as u8]);
+    }
+    hasher.finish()
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedAuthor {
+    name: String,
+    email: String,
+    /// Each commit the author is credited for, with the bits of its `Kinds`.
+    commits: Vec<(String, u8)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedAuthorMap {
+    format: u32,
+    authors: Vec<CachedAuthor>,
+    unknown_reviewers: CachedOccurrences,
+    malformed_reviewers: CachedOccurrences,
+    automation: CachedOccurrences,
+    problems: CachedOccurrences,
+}
+
+/// Each name, with the commits it was found in.
+#[derive(Serialize, Deserialize)]
+struct CachedOccurrences(Vec<(String, Vec<String>)>);
+
+impl From<&Occurrences> for CachedOccurrences {
+    fn from(occurrences: &Occurrences) -> Self {
+        CachedOccurrences(
+            occurrences
+                .iter()
+                .map(|(name, commits)| {
+                    let commits = commits.iter().map(|oid| oid.to_string()).collect();
+                    (name.to_string(), commits)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl TryFrom<CachedOccurrences> for Occurrences {
+    type Error = git2::Error;
+
+    fn try_from(cached: CachedOccurrences) -> Result<Self, Self::Error> {
+        let mut occurrences = Occurrences::default();
+        for (name, commits) in cached.0 {
+            for commit in commits {
+                occurrences.add(name.clone(), Oid::from_str(&commit)?);
+            }
+        }
+        Ok(occurrences)
+    }
+}
+
+impl From<&AuthorMap> for CachedAuthorMap {
+    fn from(map: &AuthorMap) -> Self {
+        let authors = map
+            .map
+            .iter()
+            .map(|(author, commits)| CachedAuthor {
+                name: author.name.to_string(),
+                email: author.email.to_string(),
+                commits: commits
+                    .iter()
+                    .map(|(oid, kinds)| (oid.to_string(), kinds.0))
+                    .collect(),
+            })
+            .collect();
+        CachedAuthorMap {
+            format: CACHE_FORMAT,
+            authors,
+            unknown_reviewers: (&map.unknown_reviewers).into(),
+            malformed_reviewers: (&map.malformed_reviewers).into(),
+            automation: (&map.automation).into(),
+            problems: (&map.problems).into(),
+        }
+    }
+}
+
+impl TryFrom<CachedAuthorMap> for AuthorMap {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(cached: CachedAuthorMap) -> Result<Self, Self::Error> {
+        let mut map = AuthorMap::new();
+        for author in cached.authors {
+            let commits = author
+                .commits
+                .iter()
+                .map(|(oid, kinds)| Ok((Oid::from_str(oid)?, Kinds(*kinds))))
+                .collect::<Result<HashMap<_, _>, git2::Error>>()?;
+            map.map
+                .insert(Author::new(author.name, author.email), commits);
+        }
+        map.unknown_reviewers = cached.unknown_reviewers.try_into()?;
+        map.malformed_reviewers = cached.malformed_reviewers.try_into()?;
+        map.automation = cached.automation.try_into()?;
+        map.problems = cached.problems.try_into()?;
+        Ok(map)
+    }
+}
+
+impl Cache {
+    pub fn open(settings: &CacheSettings) -> Result<Cache, Box<dyn std::error::Error>> {
+        fs::create_dir_all(&settings.directory)?;
+        Ok(Cache {
+            directory: settings.directory.clone(),
+            used: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn path(&self, fingerprint: u64, previous: Option<Oid>, release: Oid) -> PathBuf {
+        let mut hasher = StableHasher::new();
+        hasher.write(&fingerprint.to_le_bytes());
+        if let Some(previous) = previous {
+            hasher.write(previous.as_bytes());
+        }
+        hasher.write(release.as_bytes());
+        let path = self
+            .directory
+            .join(format!("{:016x}.json", hasher.finish()));
+        self.used.lock().unwrap().insert(path.clone());
+        path
+    }
+
+    /// Load the contributors to the release at `release`, since the release at `previous`.
+    ///
+    /// Entries that can't be read are treated as missing.
+    pub fn load(&self, fingerprint: u64, previous: Option<Oid>, release: Oid) -> Option<AuthorMap> {
+        let path = self.path(fingerprint, previous, release);
+        let load = || -> Result<Option<AuthorMap>, Box<dyn std::error::Error>> {
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            let cached: CachedAuthorMap = serde_json::from_slice(&contents)?;
+            if cached.format != CACHE_FORMAT {
+                return Ok(None);
+            }
+            Ok(Some(cached.try_into()?))
+        };
+        load().unwrap_or_else(|e| {
+            eprintln!("warning: ignoring cache entry {}: {}", path.display(), e);
+            None
+        })
+    }
+
+    /// Store the contributors to the release at `release`, since the release at `previous`.
+    pub fn store(
+        &self,
+        fingerprint: u64,
+        previous: Option<Oid>,
+        release: Oid,
+        map: &AuthorMap,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path(fingerprint, previous, release);
+        // Write to a temporary file first so an interrupted run can't leave a
+        // truncated entry behind.
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&CachedAuthorMap::from(map))?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Remove every entry that wasn't used during this run, along with any
+    /// temporary files left behind by interrupted runs.
+    ///
+    /// Anything else in the directory is left alone.
+    pub fn prune(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let used = self.used.lock().unwrap();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_file() && is_entry_name(&path) && !used.contains(&path) {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a file is named like a cache entry, or its temporary file.
+fn is_entry_name(path: &Path) -> bool {
+    let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+        return false;
+    };
+    let stem = stem.to_string_lossy();
+    stem.len() == 16
+        && stem.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && (extension == "json" || extension == "tmp")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prune_only_removes_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().join("cache");
+        let cache = Cache::open(&CacheSettings {
+            directory: directory.clone(),
+        })
+        .unwrap();
+        let unused = directory.join("0123456789abcdef.json");
+        let leftover = directory.join("0123456789abcdef.tmp");
+        let other = directory.join("notes.json");
+        for file in [&unused, &leftover, &other] {
+            fs::write(file, "").unwrap();
+        }
+        fs::create_dir(directory.join("fedcba9876543210.json")).unwrap();
+        let used = cache.path(1, None, Oid::zero());
+        fs::write(&used, "").unwrap();
+
+        cache.prune().unwrap();
+        let mut left = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        left.sort();
+        let mut expected = vec![
+            used.file_name().unwrap().to_str().unwrap().to_string(),
+            "fedcba9876543210.json".into(),
+            "notes.json".into(),
+        ];
+        expected.sort();
+        assert_eq!(left, expected);
+    }
+}
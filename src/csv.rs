@@ -0,0 +1,181 @@
+//! CSV output following RFC 4180.
+//!
+//! Every file starts with a header row naming the columns, which are chosen by
+//! the `[csv]` section of the settings. Fields containing a comma, quote or line
+//! break are quoted, with quotes doubled, and lines end in CRLF.
+
+use crate::score::AuthorScore;
+use crate::settings::Settings;
+use crate::{AuthorsWithScores, ProjectThanks};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// A field of [`AuthorScore`] which can be written as a column.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Column {
+    Rank,
+    Author,
+    Email,
+    Commits,
+    Authored,
+    Reviewed,
+    CoAuthored,
+    Tested,
+    Reported,
+    Suggested,
+    Slug,
+}
+
+impl Column {
+    /// The name of the column in the header row, the same as in the settings.
+    fn name(self) -> &'static str {
+        match self {
+            Column::Rank => "rank",
+            Column::Author => "author",
+            Column::Email => "email",
+            Column::Commits => "commits",
+            Column::Authored => "authored",
+            Column::Reviewed => "reviewed",
+            Column::CoAuthored => "co-authored",
+            Column::Tested => "tested",
+            Column::Reported => "reported",
+            Column::Suggested => "suggested",
+            Column::Slug => "slug",
+        }
+    }
+
+    fn value(self, score: &AuthorScore) -> Cow<'_, str> {
+        match self {
+            Column::Rank => score.rank.to_string().into(),
+            Column::Author => escape(&score.author),
+            Column::Email => escape(&score.email),
+            Column::Commits => score.commits.to_string().into(),
+            Column::Authored => score.authored.to_string().into(),
+            Column::Reviewed => score.reviewed.to_string().into(),
+            Column::CoAuthored => score.co_authored.to_string().into(),
+            Column::Tested => score.tested.to_string().into(),
+            Column::Reported => score.reported.to_string().into(),
+            Column::Suggested => score.suggested.to_string().into(),
+            Column::Slug => escape(&score.slug),
+        }
+    }
+}
+
+/// Quote a field if it contains characters with special meaning in CSV.
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+fn write_row<'a>(
+    out: &mut impl Write,
+    fields: impl Iterator<Item = Cow<'a, str>>,
+) -> std::io::Result<()> {
+    for (idx, field) in fields.enumerate() {
+        if idx != 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(field.as_bytes())?;
+    }
+    out.write_all(b"\r\n")
+}
+
+fn write_scores(
+    out: &mut impl Write,
+    columns: &[Column],
+    scores: &[AuthorScore],
+) -> std::io::Result<()> {
+    write_row(out, columns.iter().map(|c| c.name().into()))?;
+    for score in scores {
+        write_row(out, columns.iter().map(|c| c.value(score)))?;
+    }
+    Ok(())
+}
+
+fn write_file(
+    path: &Path,
+    columns: &[Column],
+    authors: &AuthorsWithScores,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    write_scores(&mut file, columns, &authors.scores)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Write `csv/<slug>/<version>.csv` and `csv/<slug>/all-time.csv` for every
+/// project, and `csv/all-time.csv` combining them.
+///
+/// With a single project its files are written straight to `csv/`, where they
+/// were before projects were configurable.
+pub fn write(
+    settings: &Settings,
+    projects: &[ProjectThanks<'_>],
+    all_time: &AuthorsWithScores,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let columns = &settings.csv.columns;
+    let directory = settings.site.output.join("csv");
+    for project in projects {
+        let directory = if projects.len() == 1 {
+            directory.clone()
+        } else {
+            directory.join(&project.project.slug)
+        };
+        fs::create_dir_all(&directory)?;
+        for (version, authors) in &project.by_version {
+            write_file(&directory.join(format!("{version}.csv")), columns, authors)?;
+        }
+        write_file(&directory.join("all-time.csv"), columns, &project.all_time)?;
+    }
+    if projects.len() > 1 {
+        write_file(&directory.join("all-time.csv"), columns, all_time)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape_plain() {
+        assert_eq!(escape("Jane Doe"), "Jane Doe");
+    }
+
+    #[test]
+    fn escape_special() {
+        assert_eq!(escape("Doe, Jane"), "\"Doe, Jane\"");
+        assert_eq!(escape("Jane \"JD\" Doe"), "\"Jane \"\"JD\"\" Doe\"");
+        assert_eq!(escape("Jane\nDoe"), "\"Jane\nDoe\"");
+    }
+
+    #[test]
+    fn scores() {
+        let score = AuthorScore {
+            rank: 1,
+            author: "Doe, Jane".into(),
+            email: "jane@example.com".into(),
+            commits: 3,
+            authored: 2,
+            reviewed: 1,
+            co_authored: 0,
+            tested: 0,
+            reported: 0,
+            suggested: 0,
+            slug: "0123456789abcdef".into(),
+        };
+        let mut out = Vec::new();
+        write_scores(&mut out, &[Column::Author, Column::Commits], &[score]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "author,commits\r\n\"Doe, Jane\",3\r\n"
+        );
+    }
+}
@@ -2,7 +2,6 @@ use std::error::Error;
 use std::fmt;
 
 #[derive(Debug)]
-#[allow(unused)]
 pub struct ErrorMessage(pub String);
 
 impl fmt::Display for ErrorMessage {
@@ -13,6 +12,29 @@ impl fmt::Display for ErrorMessage {
 
 impl Error for ErrorMessage {}
 
+/// Something in a commit which can't be read as expected.
+///
+/// Runs skip over the affected part of the commit and warn about these at the
+/// end, unless running with `--strict`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitError {
+    /// A bors merge commit naming no reviewers.
+    MissingReviewers,
+    /// An author whose name or email isn't valid UTF-8.
+    InvalidSignature,
+}
+
+impl fmt::Display for CommitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommitError::MissingReviewers => write!(f, "bors merge commit names no reviewers"),
+            CommitError::InvalidSignature => write!(f, "author isn't valid UTF-8"),
+        }
+    }
+}
+
+impl Error for CommitError {}
+
 #[derive(Debug)]
 pub struct ErrorContext(pub String, pub Box<dyn std::error::Error>);
 
@@ -27,3 +49,40 @@ impl Error for ErrorContext {
         Some(&*self.1)
     }
 }
+
+/// A copy of an error and its causes which, unlike `Box<dyn Error>`, can be sent
+/// between threads.
+#[derive(Debug)]
+pub struct ErrorChain {
+    message: String,
+    source: Option<Box<ErrorChain>>,
+}
+
+impl ErrorChain {
+    pub fn new(err: &dyn Error) -> Self {
+        ErrorChain {
+            message: err.to_string(),
+            source: err.source().map(|source| Box::new(ErrorChain::new(source))),
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for ErrorChain {
+    fn from(err: Box<dyn Error>) -> Self {
+        ErrorChain::new(&*err)
+    }
+}
+
+impl fmt::Display for ErrorChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ErrorChain {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
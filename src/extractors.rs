@@ -0,0 +1,246 @@
+//! Finding who reviewed a commit, for each way projects record it.
+//!
+//! Each project lists the extractors to try in `reviewer-extractors`, in order,
+//! and the first one recognizing a commit decides its reviewers. A commit none
+//! of them recognize has no reviewers.
+
+use crate::error::CommitError;
+use crate::is_rollup_commit;
+use crate::settings::ExtractorKind;
+use crate::trailers;
+use git2::Commit;
+use mailmap::Author;
+use std::borrow::Cow;
+
+/// Someone named as a reviewer in a commit.
+#[derive(Debug, PartialEq)]
+pub enum Reviewer<'c> {
+    /// A list of GitHub usernames, as in `r=alice,bob`, which are resolved
+    /// through [`Reviewers`](crate::reviewers::Reviewers).
+    Usernames(Cow<'c, str>),
+    /// Someone identified by name and email, as in `Jane Doe <jane@example.com>`.
+    Person(Author),
+}
+
+/// Finds the reviewers of commits recorded in one particular way.
+pub trait ReviewerExtractor: Sync {
+    /// The reviewers named in `commit`, or `None` if it isn't a commit this
+    /// extractor handles.
+    fn reviewers<'c>(
+        &self,
+        commit: &'c Commit<'_>,
+    ) -> Result<Option<Vec<Reviewer<'c>>>, CommitError>;
+}
+
+impl ExtractorKind {
+    pub fn extractor(self) -> &'static dyn ReviewerExtractor {
+        match self {
+            ExtractorKind::Bors => &ClassicBors,
+            ExtractorKind::RustBors => &RustBors,
+            ExtractorKind::MergeQueue => &MergeQueue,
+            ExtractorKind::Trailers => &Trailers,
+        }
+    }
+}
+
+/// Merges by the original bors (homu), and rollups of them merged through
+/// GitHub, which credit the reviewers after ` r=` or on a `Reviewed-by: ` line.
+///
+/// If a merge has neither, its message must be exactly "automated merge\n", as
+/// used by the earliest versions of bors, otherwise it's an error.
+struct ClassicBors;
+
+impl ReviewerExtractor for ClassicBors {
+    fn reviewers<'c>(
+        &self,
+        commit: &'c Commit<'_>,
+    ) -> Result<Option<Vec<Reviewer<'c>>>, CommitError> {
+        let is_bors =
+            commit.author().name_bytes() == b"bors" && commit.committer().name_bytes() == b"bors";
+        if !is_bors && (commit.committer().name_bytes() != b"GitHub" || !is_rollup_commit(commit)) {
+            return Ok(None);
+        }
+        // Skip non-merge commits
+        if commit.parent_count() == 1 {
+            return Ok(None);
+        }
+
+        let message = commit.message().unwrap_or("");
+        match homu_reviewers(message) {
+            Some(list) => Ok(Some(vec![Reviewer::Usernames(list.into())])),
+            // old bors didn't include r=
+            None if message == "automated merge\n" => Ok(None),
+            None => Err(CommitError::MissingReviewers),
+        }
+    }
+}
+
+/// Merges by the rewritten bors, which use the same format as the original.
+///
+/// On rust-lang/rust, it merged as `rust-bors[bot]` for about a week from
+/// January 7 to January 12 2026.
+struct RustBors;
+
+impl ReviewerExtractor for RustBors {
+    fn reviewers<'c>(
+        &self,
+        commit: &'c Commit<'_>,
+    ) -> Result<Option<Vec<Reviewer<'c>>>, CommitError> {
+        if commit.author().name_bytes() != b"rust-bors[bot]" || commit.parent_count() == 1 {
+            return Ok(None);
+        }
+        match homu_reviewers(commit.message().unwrap_or("")) {
+            Some(list) => Ok(Some(vec![Reviewer::Usernames(list.into())])),
+            None => Err(CommitError::MissingReviewers),
+        }
+    }
+}
+
+/// Pull requests merged through GitHub, including its merge queue.
+///
+/// GitHub doesn't record who approved a pull request in the commit merging it,
+/// so the reviewers are taken from `Reviewed-by:` or `Approved-by:` trailers,
+/// which the project's merge message template has to include.
+struct MergeQueue;
+
+impl ReviewerExtractor for MergeQueue {
+    fn reviewers<'c>(
+        &self,
+        commit: &'c Commit<'_>,
+    ) -> Result<Option<Vec<Reviewer<'c>>>, CommitError> {
+        if commit.committer().name_bytes() != b"GitHub" {
+            return Ok(None);
+        }
+        let summary = commit.summary_bytes().unwrap_or(b"");
+        // Merge commits, and squashed commits titled `Title (#123)`.
+        let is_pull_request = summary.starts_with(b"Merge pull request #")
+            || (summary.ends_with(b")") && summary.windows(2).any(|w| w == b"(#"));
+        if !is_pull_request {
+            return Ok(None);
+        }
+        let message = commit.message().unwrap_or("");
+        let found = trailer_reviewers(message, &["Reviewed-by", "Approved-by"]);
+        // Leave pull requests without them to the extractors after this one.
+        Ok((!found.is_empty()).then_some(found))
+    }
+}
+
+/// Any commit with `Reviewed-by:` or `Acked-by:` trailers, as used by projects
+/// following the Linux kernel's conventions.
+struct Trailers;
+
+impl ReviewerExtractor for Trailers {
+    fn reviewers<'c>(
+        &self,
+        commit: &'c Commit<'_>,
+    ) -> Result<Option<Vec<Reviewer<'c>>>, CommitError> {
+        let found = trailer_reviewers(commit.message().unwrap_or(""), &["Reviewed-by", "Acked-by"]);
+        Ok((!found.is_empty()).then_some(found))
+    }
+}
+
+/// The reviewers in a bors merge message: those after the first ` r=`, or
+/// else those on the first `Reviewed-by: ` line.
+fn homu_reviewers(message: &str) -> Option<&str> {
+    if let Some(line) = message.lines().find(|l| l.contains(" r=")) {
+        let start = line.find("r=").unwrap() + 2;
+        let end = line[start..]
+            .find(' ')
+            .map(|pos| pos + start)
+            .unwrap_or(line.len());
+        Some(&line[start..end])
+    } else {
+        message
+            .lines()
+            .find_map(|l| l.strip_prefix("Reviewed-by: "))
+    }
+}
+
+/// The values of the trailers with one of the given keys (ignoring case), see
+/// [`trailers::parse`].
+///
+/// Values of the form `Name <email>` name a person; anything else is taken as
+/// a list of usernames.
+fn trailer_reviewers(message: &str, keys: &[&str]) -> Vec<Reviewer<'static>> {
+    let is_key = |key: &str| keys.iter().any(|k| k.eq_ignore_ascii_case(key));
+    trailers::parse(message, is_key)
+        .into_iter()
+        .filter(|trailer| is_key(trailer.key) && !trailer.value.is_empty())
+        .map(|trailer| match trailers::person(&trailer.value) {
+            Some(author) => Reviewer::Person(author),
+            None => Reviewer::Usernames(trailer.value.into()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn homu() {
+        assert_eq!(
+            homu_reviewers("Auto merge of #1 - feat, r=alice,bob\n\nbody"),
+            Some("alice,bob")
+        );
+        assert_eq!(
+            homu_reviewers("Auto merge of #2 - q, r=brson! extra"),
+            Some("brson!")
+        );
+        assert_eq!(
+            homu_reviewers("Merge\n\nReviewed-by: alice\n"),
+            Some("alice")
+        );
+        assert_eq!(homu_reviewers("automated merge\n"), None);
+    }
+
+    #[test]
+    fn trailers() {
+        let message = "Fix things\n\nReviewed-by: not a trailer, not the last paragraph\n\n\
+                       Signed-off-by: Jane <jane@example.com>\n\
+                       Reviewed-by: Jane Doe <jane@example.com>\n\
+                       acked-by: alice, bob\n\
+                       Acked-by:\n";
+        assert_eq!(
+            trailer_reviewers(message, &["Reviewed-by", "Acked-by"]),
+            [
+                Reviewer::Person(Author::new("Jane Doe".into(), "jane@example.com".into())),
+                Reviewer::Usernames("alice, bob".into()),
+            ]
+        );
+        assert_eq!(
+            trailer_reviewers("Reviewed-by: alice", &["Reviewed-by"]),
+            []
+        );
+    }
+
+    #[test]
+    fn merge_queue_without_approvals() {
+        let directory = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(directory.path()).unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let author = git2::Signature::now("Jane", "jane@example.com").unwrap();
+        let github = git2::Signature::now("GitHub", "noreply@github.com").unwrap();
+        let commit = |message: &str| {
+            let oid = repo
+                .commit(None, &author, &github, message, &tree, &[])
+                .unwrap();
+            repo.find_commit(oid).unwrap()
+        };
+
+        let approved = commit("Fix things (#1)\n\nApproved-by: alice\n");
+        assert_eq!(
+            MergeQueue.reviewers(&approved).unwrap(),
+            Some(vec![Reviewer::Usernames("alice".into())])
+        );
+        // Without approvals, later extractors get to look at the commit.
+        let acked = commit("Fix things (#2)\n\nAcked-by: bob\n");
+        assert_eq!(MergeQueue.reviewers(&acked).unwrap(), None);
+        assert_eq!(
+            Trailers.reviewers(&acked).unwrap(),
+            Some(vec![Reviewer::Usernames("bob".into())])
+        );
+    }
+}
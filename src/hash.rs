@@ -0,0 +1,29 @@
+/// A 64-bit FNV-1a hasher whose output is stable across runs, platforms and
+/// Rust versions, unlike `std::hash::DefaultHasher`.
+///
+/// Used wherever a hash ends up on disk or in a URL.
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    pub fn new() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Hash a string, prefixed by its length so that consecutive strings can't
+    /// run into each other.
+    pub fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,280 @@
+//! Machine-readable output for other tools to consume.
+//!
+//! Everything is written below `<output>/json/`:
+//!
+//! * `index.json` is a manifest listing every project and version, with the
+//!   number of people and contributions in each and the path of its document.
+//! * `<project>/<version>.json` and `<project>/all-time.json` list the
+//!   contributors of a single version, or of all versions of a project.
+//! * `all-time.json` lists the contributors across all projects.
+//!
+//! Every document carries `schema_version`, which is bumped whenever a field is
+//! removed or changes meaning. New fields may be added without a bump.
+
+use crate::score::AuthorScore;
+use crate::settings::Settings;
+use crate::{AuthorsWithScores, ProjectThanks};
+use serde::Serialize;
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Index<'a> {
+    schema_version: u32,
+    all_time: Summary,
+    projects: Vec<Project<'a>>,
+}
+
+#[derive(Serialize)]
+struct Project<'a> {
+    name: &'a str,
+    slug: &'a str,
+    all_time: Summary,
+    versions: Vec<Version>,
+}
+
+#[derive(Serialize)]
+struct Version {
+    version: String,
+    name: String,
+    in_progress: bool,
+    #[serde(flatten)]
+    summary: Summary,
+}
+
+/// Totals of a single document, and where to find it relative to `index.json`.
+#[derive(Serialize)]
+struct Summary {
+    path: String,
+    people: usize,
+    contributions: usize,
+}
+
+impl Summary {
+    fn new(path: String, authors: &AuthorsWithScores) -> Self {
+        Summary {
+            path,
+            people: authors.people(),
+            contributions: authors.contributions(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Contributors<'a> {
+    schema_version: u32,
+    project: Option<&'a str>,
+    version: Option<String>,
+    name: String,
+    in_progress: bool,
+    people: usize,
+    contributions: usize,
+    contributors: Vec<Contributor<'a>>,
+}
+
+#[derive(Serialize)]
+struct Contributor<'a> {
+    rank: u32,
+    name: &'a str,
+    email: &'a str,
+    /// Identifies the person's profile page on the site.
+    slug: &'a str,
+    contributions: Counts,
+}
+
+#[derive(Serialize)]
+struct Counts {
+    total: usize,
+    authored: usize,
+    reviewed: usize,
+    co_authored: usize,
+    tested: usize,
+    reported: usize,
+    suggested: usize,
+}
+
+impl<'a> Contributors<'a> {
+    fn new(authors: &'a AuthorsWithScores, name: String, in_progress: bool) -> Self {
+        Contributors {
+            schema_version: SCHEMA_VERSION,
+            project: None,
+            version: None,
+            name,
+            in_progress,
+            people: authors.people(),
+            contributions: authors.contributions(),
+            contributors: authors.scores.iter().map(Contributor::new).collect(),
+        }
+    }
+}
+
+impl<'a> Contributor<'a> {
+    fn new(score: &'a AuthorScore) -> Self {
+        Contributor {
+            rank: score.rank,
+            name: &score.author,
+            email: &score.email,
+            slug: &score.slug,
+            contributions: Counts {
+                total: score.commits,
+                authored: score.authored,
+                reviewed: score.reviewed,
+                co_authored: score.co_authored,
+                tested: score.tested,
+                reported: score.reported,
+                suggested: score.suggested,
+            },
+        }
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let file = BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer_pretty(file, value)?;
+    Ok(())
+}
+
+pub fn write(
+    settings: &Settings,
+    projects: &[ProjectThanks<'_>],
+    all_time: &AuthorsWithScores,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let directory = settings.site.output.join("json");
+    fs::create_dir_all(&directory)?;
+
+    let mut index = Index {
+        schema_version: SCHEMA_VERSION,
+        all_time: Summary::new("all-time.json".into(), all_time),
+        projects: Vec::new(),
+    };
+    write_json(
+        &directory.join("all-time.json"),
+        &Contributors::new(all_time, format!("All-time {}", settings.site.name), true),
+    )?;
+
+    for project in projects {
+        let name = &project.project.name;
+        let slug = &project.project.slug;
+        fs::create_dir_all(directory.join(slug))?;
+
+        let path = format!("{slug}/all-time.json");
+        write_json(
+            &directory.join(&path),
+            &Contributors {
+                project: Some(slug),
+                ..Contributors::new(&project.all_time, format!("All-time {name}"), true)
+            },
+        )?;
+        let mut entry = Project {
+            name,
+            slug,
+            all_time: Summary::new(path, &project.all_time),
+            versions: Vec::new(),
+        };
+
+        for (version, authors) in &project.by_version {
+            let path = format!("{slug}/{version}.json");
+            write_json(
+                &directory.join(&path),
+                &Contributors {
+                    project: Some(slug),
+                    version: Some(version.to_string()),
+                    ..Contributors::new(authors, version.name.clone(), version.in_progress)
+                },
+            )?;
+            entry.versions.push(Version {
+                version: version.to_string(),
+                name: version.name.clone(),
+                in_progress: version.in_progress,
+                summary: Summary::new(path, authors),
+            });
+        }
+        index.projects.push(entry);
+    }
+
+    write_json(&directory.join("index.json"), &index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AuthorMap, ContributionKind, VersionTag};
+    use git2::Oid;
+    use mailmap::Author;
+    use semver::Version as SemVer;
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+
+    fn read(path: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn write_documents() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        let mut settings: Settings = toml::from_str(include_str!("../thanks.toml")).unwrap();
+        settings.site.output = directory.clone();
+
+        let oid = |n: u8| Oid::from_bytes(&[n; 20]).unwrap();
+        let mut authors = AuthorMap::new();
+        let jane = Author::new("Jane".into(), "jane@example.com".into());
+        authors.add(jane.clone(), oid(1), ContributionKind::Authored);
+        authors.add(jane, oid(2), ContributionKind::Reviewed);
+        // The same email under another name is listed as one person.
+        let renamed = Author::new("Jane Doe".into(), "jane@example.com".into());
+        authors.add(renamed, oid(3), ContributionKind::Authored);
+        let john = Author::new("John".into(), "john@example.com".into());
+        authors.add(john, oid(3), ContributionKind::Authored);
+
+        let version = VersionTag {
+            project: "rust".into(),
+            name: "Rust 1.0.0".into(),
+            version: SemVer::new(1, 0, 0),
+            raw_tag: "1.0.0".into(),
+            commit: oid(3),
+            in_progress: false,
+        };
+        let mut by_version = BTreeMap::new();
+        by_version.insert(version, AuthorsWithScores::new(authors.clone()));
+        let projects = [ProjectThanks {
+            project: &settings.projects[0],
+            by_version,
+            all_time: AuthorsWithScores::new(authors.clone()),
+        }];
+        let all_time = AuthorsWithScores::new(authors);
+        write(&settings, &projects, &all_time).unwrap();
+
+        let index = read(&directory.join("json/index.json"));
+        assert_eq!(index["schema_version"], SCHEMA_VERSION);
+        assert_eq!(index["all_time"]["path"], "all-time.json");
+        assert_eq!(index["all_time"]["people"], 2);
+        assert_eq!(index["all_time"]["contributions"], 4);
+        let project = &index["projects"][0];
+        assert_eq!(project["slug"], "rust");
+        assert_eq!(project["all_time"]["path"], "rust/all-time.json");
+        let version = &project["versions"][0];
+        assert_eq!(version["version"], "1.0.0");
+        assert_eq!(version["path"], "rust/1.0.0.json");
+        assert_eq!(version["in_progress"], false);
+        assert_eq!(version["people"], all_time.people());
+
+        let document = read(&directory.join("json/rust/1.0.0.json"));
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        assert_eq!(document["project"], "rust");
+        assert_eq!(document["version"], "1.0.0");
+        assert_eq!(document["name"], "Rust 1.0.0");
+        assert_eq!(document["people"], 2);
+        assert_eq!(document["contributions"], 4);
+        let jane = &document["contributors"][0];
+        assert_eq!(jane["rank"], 1);
+        assert_eq!(jane["email"], "jane@example.com");
+        assert_eq!(jane["contributions"]["total"], 3);
+        assert_eq!(jane["contributions"]["authored"], 2);
+        assert_eq!(jane["contributions"]["reviewed"], 1);
+        assert_eq!(document["contributors"].as_array().unwrap().len(), 2);
+    }
+}
@@ -1,25 +1,42 @@
 use config::Config;
 use git2::{Commit, Oid, Repository};
 use mailmap::{Author, Mailmap};
-use regex::{Regex, RegexBuilder};
+use rayon::prelude::*;
 use reviewers::Reviewers;
 use semver::Version;
+use settings::{
+    BotAction, BotSettings, ExtractorKind, MailmapRevision, ProjectSettings, Settings,
+    SubmoduleSettings, TrailerSettings,
+};
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::io::{BufWriter, Read};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{cmp, fmt, str};
 
+mod cache;
 mod config;
+mod csv;
 mod error;
+mod extractors;
+mod hash;
+mod json;
+mod privacy;
+mod report;
 mod reviewers;
 mod score;
+mod settings;
 mod site;
+mod suggest;
+mod trailers;
 
+use crate::cache::Cache;
+use crate::privacy::Privacy;
 use crate::score::{AuthorScore, author_map_to_scores};
-use error::ErrorContext;
+use error::{CommitError, ErrorChain, ErrorContext, ErrorMessage};
 
 /// Convert a commit signature to an `Author`.
 ///
@@ -27,25 +44,126 @@ use error::ErrorContext;
 /// allow adding an extra method to the `Author` type.
 trait ToAuthor {
     /// Convert a git commit signature to an `Author`.
-    fn from_sig(sig: git2::Signature<'_>) -> Author;
+    fn from_sig(sig: &git2::Signature<'_>) -> Result<Author, CommitError>;
+
+    /// Convert a git commit signature to an `Author`, replacing anything which
+    /// isn't valid UTF-8.
+    fn from_sig_lossy(sig: &git2::Signature<'_>) -> Author;
 }
 
 impl ToAuthor for Author {
-    fn from_sig(sig: git2::Signature<'_>) -> Author {
-        let name = sig.name().unwrap_or_else(|| panic!("no name for {}", sig));
-        let email = sig
-            .email()
-            .unwrap_or_else(|| panic!("no email for {}", sig));
+    fn from_sig(sig: &git2::Signature<'_>) -> Result<Author, CommitError> {
+        match (sig.name(), sig.email()) {
+            (Some(name), Some(email)) => Ok(Author::new(name.to_string(), email.to_string())),
+            _ => Err(CommitError::InvalidSignature),
+        }
+    }
+
+    fn from_sig_lossy(sig: &git2::Signature<'_>) -> Author {
+        Author::new(
+            String::from_utf8_lossy(sig.name_bytes()).into_owned(),
+            String::from_utf8_lossy(sig.email_bytes()).into_owned(),
+        )
+    }
+}
+
+/// The ways in which someone can be credited for a commit.
+///
+/// Besides authoring a commit, these are the kinds people named in its
+/// trailers can be credited with (see [`TrailerSettings`]).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContributionKind {
+    /// The git author of the commit.
+    Authored,
+    /// A reviewer named in a bors merge commit, or in a `Reviewed-by:` trailer.
+    Reviewed,
+    /// Listed in a `Co-authored-by:` trailer of the commit message.
+    CoAuthored,
+    /// Listed in a `Tested-by:` trailer.
+    Tested,
+    /// Listed in a `Reported-by:` trailer, for reporting the bug the commit fixes.
+    Reported,
+    /// Listed in a `Suggested-by:` trailer.
+    Suggested,
+}
+
+/// The set of [`ContributionKind`]s someone is credited with for one commit.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+struct Kinds(u8);
+
+impl Kinds {
+    fn bit(kind: ContributionKind) -> u8 {
+        1 << kind as u8
+    }
+
+    fn insert(&mut self, kind: ContributionKind) {
+        self.0 |= Kinds::bit(kind);
+    }
+
+    fn contains(self, kind: ContributionKind) -> bool {
+        self.0 & Kinds::bit(kind) != 0
+    }
 
-        Author::new(name.to_string(), email.to_string())
+    fn union(self, other: Kinds) -> Kinds {
+        Kinds(self.0 | other.0)
     }
 }
 
+/// How many commits someone is credited for, in total and by kind.
+///
+/// A commit can be credited in more than one way, e.g. when someone both
+/// authored and reviewed it, so the kinds don't necessarily add up to `commits`.
+#[derive(Copy, Clone, Default)]
+pub struct Contributions {
+    pub commits: usize,
+    pub authored: usize,
+    pub reviewed: usize,
+    pub co_authored: usize,
+    pub tested: usize,
+    pub reported: usize,
+    pub suggested: usize,
+}
+
 /// Map authors to their commits.
 #[derive(Clone)]
 pub struct AuthorMap {
-    /// Mapping of each Author to the commits they authored or co-authored.
-    map: HashMap<Author, HashSet<Oid>>,
+    /// Mapping of each Author to the commits they are credited for, and how.
+    map: HashMap<Author, HashMap<Oid, Kinds>>,
+    /// Reviewers named in merge commits which couldn't be resolved to anyone,
+    /// lowercased.
+    unknown_reviewers: Occurrences,
+    /// Reviewers named in merge commits with characters which don't appear in
+    /// GitHub usernames, usually a sign of a misparsed `r=` line.
+    malformed_reviewers: Occurrences,
+    /// Bots credited for commits, as `Name <email>`, which are reported
+    /// instead of ranked.
+    automation: Occurrences,
+    /// Commits which couldn't be fully read, by the description of the
+    /// [`CommitError`].
+    problems: Occurrences,
+}
+
+/// Names found in commits, each with the commits it was found in.
+#[derive(Clone, Default)]
+struct Occurrences(HashMap<String, HashSet<Oid>>);
+
+impl Occurrences {
+    fn add(&mut self, name: String, commit: Oid) {
+        self.0.entry(name).or_default().insert(commit);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &HashSet<Oid>)> {
+        self.0
+            .iter()
+            .map(|(name, commits)| (name.as_str(), commits))
+    }
+
+    fn extend(&mut self, other: Occurrences) {
+        for (name, commits) in other.0 {
+            self.0.entry(name).or_default().extend(commits);
+        }
+    }
 }
 
 impl AuthorMap {
@@ -53,46 +171,95 @@ impl AuthorMap {
     fn new() -> Self {
         AuthorMap {
             map: HashMap::new(),
+            unknown_reviewers: Occurrences::default(),
+            malformed_reviewers: Occurrences::default(),
+            automation: Occurrences::default(),
+            problems: Occurrences::default(),
         }
     }
 
-    /// Add a commit authored or co-authored by the given `Author`.
+    /// Credit the given `Author` with a commit.
     ///
     /// If the author is not already included in the map, they are added.
-    fn add(&mut self, author: Author, commit: Oid) {
-        self.map.entry(author).or_default().insert(commit);
+    fn add(&mut self, author: Author, commit: Oid, kind: ContributionKind) {
+        self.map
+            .entry(author)
+            .or_default()
+            .entry(commit)
+            .or_default()
+            .insert(kind);
     }
 
-    /// Iterate over each author and the number of commits that they (co-)authored.
+    /// Iterate over each author and the number of commits that they are credited for.
     fn iter(&self) -> impl Iterator<Item = (&Author, usize)> {
         self.map.iter().map(|(k, v)| (k, v.len()))
     }
 
+    /// Iterate over each author and the number of commits they are credited
+    /// for, broken down by kind.
+    fn contributions(&self) -> impl Iterator<Item = (&Author, Contributions)> {
+        self.map.iter().map(|(author, commits)| {
+            let count = |kind| commits.values().filter(|k| k.contains(kind)).count();
+            let contributions = Contributions {
+                commits: commits.len(),
+                authored: count(ContributionKind::Authored),
+                reviewed: count(ContributionKind::Reviewed),
+                co_authored: count(ContributionKind::CoAuthored),
+                tested: count(ContributionKind::Tested),
+                reported: count(ContributionKind::Reported),
+                suggested: count(ContributionKind::Suggested),
+            };
+            (author, contributions)
+        })
+    }
+
+    /// Credit the given `Author` with commits, merging with any existing credits.
+    fn add_commits(&mut self, author: Author, commits: HashMap<Oid, Kinds>) {
+        let existing = self.map.entry(author).or_default();
+        for (commit, kinds) in commits {
+            let entry = existing.entry(commit).or_default();
+            *entry = entry.union(kinds);
+        }
+    }
+
     /// Merge in the authorship data from another instance.
     fn extend(&mut self, other: Self) {
-        for (author, set) in other.map {
-            self.map.entry(author).or_default().extend(set);
+        for (author, commits) in other.map {
+            self.add_commits(author, commits);
         }
+        self.unknown_reviewers.extend(other.unknown_reviewers);
+        self.malformed_reviewers.extend(other.malformed_reviewers);
+        self.automation.extend(other.automation);
+        self.problems.extend(other.problems);
     }
 
-    /// Create a new `AuthorMap` containing just the commits present in the current
-    /// map but not the other one.
+    /// Replace every author with the one returned by `f`, merging the credits
+    /// of authors replaced by the same one and dropping those it returns `None` for.
     #[must_use]
-    fn difference(&self, other: &AuthorMap) -> AuthorMap {
+    fn map_authors(self, mut f: impl FnMut(Author) -> Option<Author>) -> AuthorMap {
         let mut new = AuthorMap::new();
         new.map.reserve(self.map.len());
-        for (author, set) in self.map.iter() {
-            if let Some(other_set) = other.map.get(author) {
-                let diff: HashSet<_> = set.difference(other_set).cloned().collect();
-                if !diff.is_empty() {
-                    new.map.insert(author.clone(), diff);
-                }
-            } else {
-                new.map.insert(author.clone(), set.clone());
+        for (author, commits) in self.map {
+            if let Some(author) = f(author) {
+                new.add_commits(author, commits);
             }
         }
+        new.unknown_reviewers = self.unknown_reviewers;
+        new.malformed_reviewers = self.malformed_reviewers;
+        new.automation = self.automation;
+        new.problems = self.problems;
         new
     }
+
+    /// Replace every author with their canonical identity in `mailmap`,
+    /// merging the credits of identities belonging to the same person.
+    ///
+    /// A commit is always credited to the same identities, so this can be done
+    /// after the contributors of each release are known.
+    #[must_use]
+    fn canonicalize(self, mailmap: &Mailmap) -> AuthorMap {
+        self.map_authors(|author| Some(mailmap.canonicalize(&author)))
+    }
 }
 
 pub struct AuthorsWithScores {
@@ -105,6 +272,23 @@ impl AuthorsWithScores {
         let scores = author_map_to_scores(&authors);
         Self { authors, scores }
     }
+
+    /// The number of people listed, after merging identities sharing an email.
+    pub fn people(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// The number of contributions of all people.
+    pub fn contributions(&self) -> usize {
+        self.authors.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// The contributors to a single configured project.
+pub struct ProjectThanks<'a> {
+    pub project: &'a ProjectSettings,
+    pub by_version: BTreeMap<VersionTag, AuthorsWithScores>,
+    pub all_time: AuthorsWithScores,
 }
 
 /// Run a `git` command with the given arguments.
@@ -140,6 +324,27 @@ lazy_static::lazy_static! {
     static ref UPDATED: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
 }
 
+/// Set by the `--offline` flag, see [`is_offline()`].
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+/// Set by the `--strict` flag, which turns commits that can't be fully read,
+/// and reviewer data conflicting with the team data, into errors rather than
+/// warnings. See [`is_strict()`].
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Determine if thanks must not access the network.
+///
+/// In offline mode every repository must already be cloned, no missing commits
+/// are fetched and the team data is read from a snapshot.
+fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Determine if commits which can't be fully read should fail the run, rather
+/// than being warned about at its end.
+fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
 /// Create or update the bare clone of the git repo at the given URL
 ///
 /// If a clone of the repo already exists, it is only updated if
@@ -168,10 +373,21 @@ fn update_repo(url: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
 
     let path_s = format!("repos/{}", slug);
     let path = PathBuf::from(&path_s);
-    if !UPDATED.lock().unwrap().insert(slug.to_string()) {
+    // The lock is held until the clone is ready, so that other threads wanting
+    // the same repository wait for it instead of using a partial clone.
+    let mut updated = UPDATED.lock().unwrap();
+    if !updated.insert(slug.to_string()) {
         return Ok(path);
     }
-    if path.exists() {
+    if is_offline() {
+        if !path.exists() {
+            return Err(ErrorMessage(format!(
+                "{} has not been cloned to {}, which is required in offline mode",
+                url, path_s
+            ))
+            .into());
+        }
+    } else if path.exists() {
         if should_update() {
             // we know for sure the path_s does *not* contain .git as we strip it, so this is a safe
             // temp directory
@@ -205,6 +421,8 @@ fn should_update() -> bool {
 /// Information about a git tag or other reference to treat as a tag.
 #[derive(Clone)]
 pub struct VersionTag {
+    /// The slug of the project this version belongs to.
+    project: String,
     /// Some custom name, e.g. "Rust 1.94.0" or "Beta".
     name: String,
     /// The parsed Version for this tag.
@@ -227,6 +445,7 @@ impl fmt::Display for VersionTag {
 
 impl std::hash::Hash for VersionTag {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.project.hash(state);
         self.version.hash(state);
     }
 }
@@ -234,7 +453,7 @@ impl std::hash::Hash for VersionTag {
 impl cmp::Eq for VersionTag {}
 impl cmp::PartialEq for VersionTag {
     fn eq(&self, other: &Self) -> bool {
-        self.version == other.version
+        self.project == other.project && self.version == other.version
     }
 }
 
@@ -246,94 +465,108 @@ impl cmp::PartialOrd for VersionTag {
 
 impl cmp::Ord for VersionTag {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.version.cmp(&other.version)
+        (&self.project, &self.version).cmp(&(&other.project, &other.version))
     }
 }
 
 impl fmt::Debug for VersionTag {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.version)
+        write!(f, "{} {}", self.project, self.version)
     }
 }
 
-fn get_versions(repo: &Repository) -> Result<Vec<VersionTag>, Box<dyn std::error::Error>> {
+fn get_versions(
+    repo: &Repository,
+    project: &ProjectSettings,
+) -> Result<Vec<VersionTag>, Box<dyn std::error::Error>> {
     let tags = repo
         .tag_names(None)?
         .into_iter()
         .flatten()
         .map(|v| v.to_owned())
         .collect::<Vec<_>>();
-    let mut versions = tags
-        .iter()
-        .filter_map(|tag| {
-            Version::parse(tag)
-                .or_else(|_| Version::parse(&format!("{}.0", tag)))
-                .ok()
-                .map(|v| VersionTag {
-                    name: format!("Rust {}", v),
-                    version: v,
-                    raw_tag: tag.clone(),
-                    commit: repo
-                        .revparse_single(tag)
-                        .unwrap()
-                        .peel_to_commit()
-                        .unwrap()
-                        .id(),
-                    in_progress: false,
-                })
-        })
-        .collect::<Vec<_>>();
+    let mut versions = Vec::new();
+    for tag in &tags {
+        let Some(version) = tag.strip_prefix(&project.tags.prefix) else {
+            continue;
+        };
+        let Ok(version) =
+            Version::parse(version).or_else(|_| Version::parse(&format!("{}.0", version)))
+        else {
+            continue;
+        };
+        versions.push(VersionTag {
+            project: project.slug.clone(),
+            name: format!("{} {}", project.name, version),
+            version,
+            raw_tag: tag.clone(),
+            commit: resolve_commit(repo, project, tag)?,
+            in_progress: false,
+        });
+    }
     versions.sort();
     Ok(versions)
 }
 
-/// Identify the co-authors, if any, of a commit
+/// Look up the commit `refname` points to in the repository of `project`.
+fn resolve_commit(
+    repo: &Repository,
+    project: &ProjectSettings,
+    refname: &str,
+) -> Result<Oid, Box<dyn std::error::Error>> {
+    repo.revparse_single(refname)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id())
+        .map_err(|e| {
+            ErrorMessage(format!(
+                "cannot resolve {} in the repository of {} to a commit: {}",
+                refname,
+                project.name,
+                e.message()
+            ))
+            .into()
+        })
+}
+
+/// Identify the people credited in the trailers of a commit, and how.
 ///
-/// Co-authors are determined based on the commit message having lines starting
-/// `Co-authored-by: ` followed by a name and then an email enclosed in `<>`.
-fn commit_coauthors(commit: &Commit) -> Vec<Author> {
-    let mut coauthors = vec![];
-    if let Some(msg) = commit.message_raw() {
-        lazy_static::lazy_static! {
-            static ref RE: Regex =
-                RegexBuilder::new(r"^Co-authored-by: (?P<name>.*) <(?P<email>.*)>")
-                    .case_insensitive(true)
-                    .build()
-                    .unwrap();
-        }
-
-        for line in msg.lines().rev() {
-            if line.starts_with("Co-authored-by")
-                && let Some(caps) = RE.captures(line)
-            {
-                coauthors.push(Author::new(
-                    caps["name"].to_string(),
-                    caps["email"].to_string(),
-                ));
-            }
-        }
-    }
-    coauthors
+/// Every trailer whose key `trailers` maps to a [`ContributionKind`], and whose
+/// value is a name followed by an email enclosed in `<>`, credits that person.
+fn commit_trailer_credits(
+    trailers: &TrailerSettings,
+    commit: &Commit,
+) -> Vec<(Author, ContributionKind)> {
+    let message = commit.message_raw().unwrap_or("");
+    trailers::parse(message, |key| trailers.kind(key).is_some())
+        .into_iter()
+        .filter_map(|trailer| {
+            let kind = trailers.kind(trailer.key)?;
+            Some((trailers::person(&trailer.value)?, kind))
+        })
+        .collect()
 }
 
-/// Build up an [`AuthorMap`] of commits authored between `from` and `to`.
+/// Build up an [`AuthorMap`] of the commits reachable from `to` but not from
+/// any of `hide`.
 ///
 /// This function is a wrapper around [`build_author_map_`] to add additional
 /// context to any errors; see that function for further documentation.
 fn build_author_map(
     repo: &Repository,
     reviewers: &Reviewers,
-    mailmap: &Mailmap,
-    from: &str,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+    extractors: &[ExtractorKind],
+    hide: &[Oid],
     to: &str,
 ) -> Result<AuthorMap, Box<dyn std::error::Error>> {
-    match build_author_map_(repo, reviewers, mailmap, from, to) {
+    match build_author_map_(repo, reviewers, bots, trailers, extractors, hide, to) {
         Ok(o) => Ok(o),
         Err(err) => Err(ErrorContext(
             format!(
-                "build_author_map(repo={}, from={:?}, to={:?})",
+                "build_author_map(repo={}, hide={:?}, to={:?})",
                 repo.path().display(),
-                from,
+                hide,
                 to
             ),
             err,
@@ -349,154 +582,124 @@ fn is_rollup_commit(commit: &Commit) -> bool {
     summary.starts_with("Rollup merge of #")
 }
 
-/// Parse a commit to identify which reviewer(s) should be created as the author
-/// of a commit
-///
-/// For commits that were not authored by bors and are neither committed by
-/// GitHub or considered rollup commits (see [`is_rollup_commit`]), no reviewers
-/// are identified.
-///
-/// For non-merge commits, no reviewers are identified.
-///
-/// For commits where at least one line of the commit message contains ` r=`,
-/// the reviewers are those listed after that ` r=`. If no such line exists,
-/// for commits where at least one line of the commit message starts with
-/// `Reviewed-by: `, the reviewers are those listed on that line. For commits
-/// where neither type of line exists, the commit message must be
-/// exactly "automated merge\n".
-///
-/// # Panics
-///
-/// For commits that are merge commits and are either authored by bors or
-/// both committed by GitHub and considered rollup commits, we try to identify
-/// reviewers. If no line of the commit message contains ` r=` or starts with
-/// `Reviewed-by: `, the commit message must be exactly "automated merge\n",
-/// otherwise panics.
-fn parse_bors_reviewer(
-    reviewers: &Reviewers,
-    repo: &Repository,
-    commit: &Commit,
-) -> Result<Option<Vec<Author>>, ErrorContext> {
-    let is_old_bors =
-        commit.author().name_bytes() == b"bors" && commit.committer().name_bytes() == b"bors";
-    // This username was used for merges for a ~week from January 7 to January 12 2026 on the
-    // rust-lang/rust repository.
-    let is_new_bors = commit.author().name_bytes() == b"rust-bors[bot]";
-    let is_bors = is_old_bors || is_new_bors;
-
-    if !is_bors && (commit.committer().name_bytes() != b"GitHub" || !is_rollup_commit(commit)) {
-        return Ok(None);
-    }
-
-    // Skip non-merge commits
-    if commit.parents().count() == 1 {
-        return Ok(None);
-    }
+/// The reviewers named in a merge commit.
+#[derive(Default)]
+struct CommitReviewers {
+    /// Reviewers resolved through [`Reviewers`].
+    known: Vec<Author>,
+    /// Names which didn't resolve to anyone, as they appear in the commit.
+    unknown: Vec<String>,
+    /// Names with characters which can't appear in a username.
+    malformed: Vec<String>,
+}
 
-    let to_author = |list: &str| -> Result<Vec<Author>, ErrorContext> {
-        list.trim_end_matches('.')
+impl CommitReviewers {
+    /// Resolve a list of usernames, as in `r=alice,bob`.
+    fn add_usernames(&mut self, reviewers: &Reviewers, list: &str) {
+        let names = list
+            .trim_end_matches('.')
             .split([',', '+'])
             .map(|r| r.trim_start_matches('@'))
             .map(|r| r.trim_end_matches('`'))
             .map(|r| r.trim())
             .filter(|r| !r.is_empty())
-            .filter(|r| *r != "<try>")
-            .inspect(|r| {
-                if !r.chars().all(|c| {
-                    c.is_alphabetic() || c.is_ascii_digit() || c == '-' || c == '_' || c == '='
-                }) {
-                    eprintln!(
-                        "warning: to_author for {} contained non-alphabetic characters: {:?}",
-                        commit.id(),
-                        r
-                    );
-                }
-            })
-            // Iterator is now of strings that are not empty, not `<try>`,
-            // do not container `,` or `+`, do not start with `@`, do not end
-            // with a '`', and do not start or end with whitespace
-            .map(|r| {
-                reviewers.to_author(r).map_err(|e| {
-                    ErrorContext(
-                        format!("reviewer: {:?}, commit: {}", r, commit.id()),
-                        e.into(),
-                    )
-                })
-            })
-            // Iterator is now of `Result<Option<Author>, ErrorContext>` items
-            .flat_map(|r| r.transpose())
-            // Each r.transpose() call returned an Option<Result<Author, ErrorContext>>
-            // for the `map` part of `flat_map()`; the `flat` part used
-            // `Option::into_iter()` to ignore the `None` options and create an
-            // iterator of `Result<Author, ErrorContext>` items.
-            // Using the `FromIterator` impl for Result<V, E>, the `collect()`
-            // call below will result in either 1) the first `ErrorContext` in
-            // the iterator, or 2) all of the `Author`s in the iterator, if there
-            // were no errors
-            .collect::<Result<Vec<_>, ErrorContext>>()
-    };
+            .filter(|r| *r != "<try>");
+        // Iterator is now of strings that are not empty, not `<try>`,
+        // do not container `,` or `+`, do not start with `@`, do not end
+        // with a '`', and do not start or end with whitespace
+        for r in names {
+            if !r.chars().all(|c| {
+                c.is_alphabetic() || c.is_ascii_digit() || c == '-' || c == '_' || c == '='
+            }) {
+                self.malformed.push(r.to_string());
+            }
+            match reviewers.to_author(r) {
+                Ok(Some(author)) => self.known.push(author),
+                Ok(None) => {}
+                Err(reviewers::UnknownReviewer) => self.unknown.push(r.to_string()),
+            }
+        }
+    }
+}
 
-    let message = commit.message().unwrap_or("");
-    let mut reviewers = if let Some(line) = message.lines().find(|l| l.contains(" r=")) {
-        let start = line.find("r=").unwrap() + 2;
-        let end = line[start..]
-            .find(' ')
-            .map(|pos| pos + start)
-            .unwrap_or(line.len());
-        to_author(&line[start..end])?
-    } else if let Some(line) = message.lines().find(|l| l.starts_with("Reviewed-by: ")) {
-        let line = &line["Reviewed-by: ".len()..];
-        to_author(line)?
-    } else {
-        // old bors didn't include r=
-        if message != "automated merge\n" {
-            panic!(
-                "expected reviewer for bors merge commit {} in {:?}, message: {:?}",
-                commit.id(),
-                repo.path(),
-                message
-            );
+/// Identify the reviewers of a commit, with the first of `extractors`
+/// recognizing it (see [`extractors`]).
+///
+/// Returns `None` if none of them do, and an error if the one recognizing it
+/// can't find its reviewers.
+fn commit_reviewers(
+    reviewers: &Reviewers,
+    extractors: &[ExtractorKind],
+    commit: &Commit,
+) -> Result<Option<CommitReviewers>, CommitError> {
+    let mut named = None;
+    for kind in extractors {
+        named = kind.extractor().reviewers(commit)?;
+        if named.is_some() {
+            break;
         }
+    }
+    let Some(named) = named else {
         return Ok(None);
     };
-    reviewers.sort();
-    reviewers.dedup();
-    Ok(Some(reviewers))
+    let mut found = CommitReviewers::default();
+    for reviewer in named {
+        match reviewer {
+            extractors::Reviewer::Usernames(list) => found.add_usernames(reviewers, &list),
+            extractors::Reviewer::Person(author) => found.known.push(author),
+        }
+    }
+    found.known.sort();
+    found.known.dedup();
+    Ok(Some(found))
 }
 
-/// Build up an [`AuthorMap`] of commits authored between `from` and `to`.
+/// Build up an [`AuthorMap`] of the commits reachable from `to` but not from
+/// any of `hide`.
 ///
-/// If `from` is empty, commits authored up to and including `to` are processed.
-/// If `from` is non-empty, commits starting *after* `from` are processed, up
-/// to and including the `to` commit.
+/// Commits in `hide` which aren't in `repo` are skipped, so the commits of
+/// every repository walked for a release can be hidden in each of them.
 ///
-/// For each commit processed, authorship is added to the `AuthorMap` result
+/// For each commit processed, credits are added to the `AuthorMap` result
 /// according to the following rules:
 /// * If the commit is **not** a rollup commit (see [`is_rollup_commit`]), the
-///   git author of the commit is credited as an author of the commit.
-/// * For every commit, any reviewers from by [`parse_bors_reviewer`] are
-///   credited as authors of the commit.
-/// * For every commit, any co-authors identified by [`commit_coauthors`] are
-///   credited as authors of the commit.
+///   git author of the commit is credited as having authored the commit.
+/// * For every commit, any reviewers identified by [`commit_reviewers`] with
+///   `extractors` are credited as having reviewed the commit.
+/// * For every commit, anyone named in its trailers is credited as
+///   `trailers` says, see [`commit_trailer_credits`].
 ///
-/// Authors in the resulting map are canonicalized using
-/// [`Mailmap::canonicalize`].
+/// Authors in the resulting map are recorded as they appear in the commits;
+/// see [`AuthorMap::canonicalize`]. Bots, as identified by `bots`, are left out
+/// of it, and recorded in its automation instead if they are to be reported.
 ///
-/// For any reviewer not recognized in [`parse_bors_reviewer`] (i.e. resulting
-/// in `Err<ErrorContext>` where the error is [`reviewers::UnknownReviewer`])
-/// a warning is printed to the standard error; any other error from
-/// [`parse_bors_reviewer`] or other methods results in returning an error.
+/// Any reviewer not recognized in [`commit_reviewers`] is recorded in the
+/// map's unknown reviewers instead, and any [`CommitError`] in its problems,
+/// to be reported at the end of the run; any other error results in returning
+/// an error.
 fn build_author_map_(
     repo: &Repository,
     reviewers: &Reviewers,
-    mailmap: &Mailmap,
-    from: &str,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+    extractors: &[ExtractorKind],
+    hide: &[Oid],
     to: &str,
 ) -> Result<AuthorMap, Box<dyn std::error::Error>> {
     let mut walker = repo.revwalk()?;
 
     if repo.revparse_single(to).is_err() {
-        // If a commit is not found, try fetching it.
+        // If a commit is not found, try fetching it. Other threads may be
+        // walking the same repository, so avoid fetching concurrently.
+        if is_offline() {
+            return Err(ErrorMessage(format!(
+                "{} not found in {}, and fetching it is disabled in offline mode",
+                to,
+                repo.path().display()
+            ))
+            .into());
+        }
+        let _updated = UPDATED.lock().unwrap();
         git(&[
             "--git-dir",
             repo.path().to_str().unwrap(),
@@ -506,11 +709,12 @@ fn build_author_map_(
         ])?;
     }
 
-    if from.is_empty() {
-        let to = repo.revparse_single(to)?.peel_to_commit()?.id();
-        walker.push(to)?;
-    } else {
-        walker.push_range(&format!("{}..{}", from, to))?;
+    let to = repo.revparse_single(to)?.peel_to_commit()?.id();
+    walker.push(to)?;
+    for &oid in hide {
+        if repo.find_commit(oid).is_ok() {
+            walker.hide(oid)?;
+        }
     }
 
     let mut author_map = AuthorMap::new();
@@ -527,89 +731,259 @@ fn build_author_map_(
             //
             // Otherwise, a single rollup with N PRs attributes N commits to the author of the
             // rollup, which isn't fair.
-            commit_authors.push(Author::from_sig(commit.author()));
-        }
-        match parse_bors_reviewer(reviewers, repo, &commit) {
-            Ok(Some(reviewers)) => commit_authors.extend(reviewers),
-            Ok(None) => {}
-            Err(ErrorContext(msg, e)) => {
-                if e.is::<reviewers::UnknownReviewer>() {
-                    eprintln!("Unknown reviewer: {}", ErrorContext(msg, e));
-                } else {
-                    return Err(ErrorContext(msg, e).into());
-                }
+            let author = Author::from_sig(&commit.author()).unwrap_or_else(|e| {
+                author_map.problems.add(e.to_string(), oid);
+                Author::from_sig_lossy(&commit.author())
+            });
+            commit_authors.push((author, ContributionKind::Authored));
+        }
+        let found = commit_reviewers(reviewers, extractors, &commit).unwrap_or_else(|e| {
+            author_map.problems.add(e.to_string(), oid);
+            None
+        });
+        if let Some(reviewers) = found {
+            commit_authors.extend(
+                reviewers
+                    .known
+                    .into_iter()
+                    .map(|r| (r, ContributionKind::Reviewed)),
+            );
+            for reviewer in reviewers.unknown {
+                author_map
+                    .unknown_reviewers
+                    .add(reviewer.to_lowercase(), oid);
+            }
+            for reviewer in reviewers.malformed {
+                author_map.malformed_reviewers.add(reviewer, oid);
             }
         }
-        commit_authors.extend(commit_coauthors(&commit));
-        for author in commit_authors {
-            let author = mailmap.canonicalize(&author);
-            author_map.add(author, oid);
+        commit_authors.extend(commit_trailer_credits(trailers, &commit));
+        for (author, kind) in commit_authors {
+            if !bots.matches(&author) {
+                author_map.add(author, oid, kind);
+            } else if bots.action == BotAction::Report {
+                let bot = format!("{} <{}>", author.name, author.email);
+                author_map.automation.add(bot, oid);
+            }
         }
     }
     Ok(author_map)
 }
 
-/// Construct a `Mailmap` based on the latest commit in the given repository.
+/// Read the `.mailmap` file from the given commit.
 ///
-/// Returns an error if the latest commit cannot be retrieved or if it does not
-/// contain a `.mailmap` file to read.
-fn mailmap_from_repo(repo: &git2::Repository) -> Result<Mailmap, Box<dyn std::error::Error>> {
-    let tree = repo.revparse_single("HEAD")?.peel_to_commit()?.tree()?;
-    let file = tree.get_name(".mailmap");
-    let file = match file {
-        None => {
-            eprintln!("No mailmap found");
-            return Mailmap::from_string("".to_string());
-        }
+/// Returns `None` if the commit does not contain a `.mailmap` file.
+fn mailmap_source(
+    repo: &git2::Repository,
+    at: &Commit<'_>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let tree = at.tree()?;
+    let file = match tree.get_name(".mailmap") {
+        None => return Ok(None),
         Some(f) => f,
     };
-    let file = String::from_utf8(file.to_object(repo)?.peel_to_blob()?.content().into())?;
-    Mailmap::from_string(file)
+    Ok(Some(String::from_utf8(
+        file.to_object(repo)?.peel_to_blob()?.content().into(),
+    )?))
+}
+
+/// One of the mailmaps applied to a project.
+struct MailmapLayer {
+    /// Where it was read from, for warnings.
+    origin: String,
+    source: String,
 }
 
-fn up_to_release(
+/// Read the mailmaps applied to a project as of the given commit, in the
+/// order they are applied in.
+///
+/// The main repository's `.mailmap` comes first, followed by those of the
+/// submodules walked at that commit (as of the commits they are pinned to) and
+/// finally the project's local overrides. Later entries win in a mailmap, so
+/// each layer can correct the ones before it without changes to the
+/// repositories themselves.
+fn mailmap_layers(
     repo: &Repository,
-    reviewers: &Reviewers,
-    mailmap: &Mailmap,
-    to: &VersionTag,
-) -> Result<AuthorMap, Box<dyn std::error::Error>> {
-    let to_commit = repo.find_commit(to.commit).map_err(|e| {
+    project: &ProjectSettings,
+    at: &Commit<'_>,
+) -> Result<Vec<MailmapLayer>, Box<dyn std::error::Error>> {
+    let mut layers = Vec::new();
+    if let Some(source) = mailmap_source(repo, at)? {
+        layers.push(MailmapLayer {
+            origin: format!(".mailmap of {} at {}", project.repository, at.id()),
+            source,
+        });
+    }
+
+    for module in get_submodules(repo, at, &project.submodules)? {
+        let subrepo = Repository::open(update_repo(&module.repository)?)?;
+        let commit = subrepo.find_commit(module.commit).map_err(|e| {
+            ErrorContext(
+                format!(
+                    "find_commit: repo={}, commit={}",
+                    module.repository, module.commit
+                ),
+                Box::new(e),
+            )
+        })?;
+        if let Some(source) = mailmap_source(&subrepo, &commit)? {
+            layers.push(MailmapLayer {
+                origin: format!(".mailmap of {} at {}", module.repository, module.commit),
+                source,
+            });
+        }
+    }
+
+    if let Some(path) = &project.mailmap_overrides {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            ErrorContext(
+                format!("reading mailmap overrides from {}", path.display()),
+                Box::new(e),
+            )
+        })?;
+        layers.push(MailmapLayer {
+            origin: path.display().to_string(),
+            source,
+        });
+    }
+    Ok(layers)
+}
+
+/// Combine the mailmaps applied to a project as of the given commit.
+///
+/// Lines git ignores are applied as git would, and pointed out if `warn` is set.
+fn mailmap_at(
+    repo: &Repository,
+    project: &ProjectSettings,
+    at: &Commit<'_>,
+    warn: bool,
+) -> Result<Mailmap, Box<dyn std::error::Error>> {
+    let layers = mailmap_layers(repo, project, at)?;
+    if warn && layers.is_empty() {
+        eprintln!("No mailmap found");
+    }
+    let mut mailmap = Mailmap::new();
+    for layer in &layers {
+        let (layer_map, problems) = Mailmap::from_string_lenient(&layer.source);
+        if warn {
+            for problem in problems {
+                eprintln!("warning: {}: {}", layer.origin, problem);
+            }
+        }
+        for entry in layer_map.entries() {
+            mailmap.add(entry.clone());
+        }
+    }
+    Ok(mailmap)
+}
+
+/// Resolve a revision of the given repository to a commit.
+fn find_revision<'r>(
+    repo: &'r Repository,
+    revision: &str,
+) -> Result<Commit<'r>, Box<dyn std::error::Error>> {
+    let find = || repo.revparse_single(revision)?.peel_to_commit();
+    find().map_err(|e| {
         ErrorContext(
-            format!(
-                "find_commit: repo={}, commit={}",
-                repo.path().display(),
-                to.commit
-            ),
+            format!("finding {} in {}", revision, repo.path().display()),
             Box::new(e),
         )
-    })?;
-    let modules = get_submodules(repo, &to_commit)?;
+        .into()
+    })
+}
 
-    let mut author_map = build_author_map(repo, reviewers, mailmap, "", &to.raw_tag)
-        .map_err(|e| ErrorContext(format!("Up to {}", to), e))?;
+/// Find the contributors to the release `to` since the release `previous`,
+/// walking the commits of the repository and its submodules in between.
+fn since_release(
+    repo: &Repository,
+    project: &ProjectSettings,
+    reviewers: &Reviewers,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+    previous: &VersionTag,
+    to: &VersionTag,
+) -> Result<AuthorMap, Box<dyn std::error::Error>> {
+    let find = |commit: Oid| {
+        repo.find_commit(commit).map_err(|e| {
+            ErrorContext(
+                format!(
+                    "find_commit: repo={}, commit={}",
+                    repo.path().display(),
+                    commit
+                ),
+                Box::new(e),
+            )
+        })
+    };
+    let modules = get_submodules(repo, &find(to.commit)?, &project.submodules)?;
+    // Everything the previous release included is left out, in whichever of
+    // the repositories it is found.
+    let mut hide = vec![previous.commit];
+    for module in get_submodules(repo, &find(previous.commit)?, &project.submodules)? {
+        hide.push(module.commit);
+    }
 
-    for module in &modules {
-        let path = update_repo(&module.repository)?;
-        let subrepo = Repository::open(&path)?;
-        let submap =
-            build_author_map(&subrepo, reviewers, mailmap, "", &module.commit.to_string())?;
+    let mut author_map = build_author_map(
+        repo,
+        reviewers,
+        bots,
+        trailers,
+        &project.reviewer_extractors,
+        &hide,
+        &to.raw_tag,
+    )
+    .map_err(|e| ErrorContext(format!("From {} to {}", previous, to), e))?;
+
+    let submaps = modules
+        .par_iter()
+        .map(|module| {
+            let walk = || -> Result<AuthorMap, Box<dyn std::error::Error>> {
+                let path = update_repo(&module.repository)?;
+                let subrepo = Repository::open(&path)?;
+                build_author_map(
+                    &subrepo,
+                    reviewers,
+                    bots,
+                    trailers,
+                    &project.reviewer_extractors,
+                    &hide,
+                    &module.commit.to_string(),
+                )
+            };
+            walk().map_err(ErrorChain::from)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for submap in submaps {
         author_map.extend(submap);
     }
-
     Ok(author_map)
 }
 
-fn generate_thanks() -> Result<BTreeMap<VersionTag, AuthorMap>, Box<dyn std::error::Error>> {
-    let path = update_repo("https://github.com/rust-lang/rust.git")?;
+/// Find the contributors to each release of a project, as identified in the
+/// commits themselves.
+///
+/// Returns the path to the project's repository along with them.
+fn walk_releases(
+    project: &ProjectSettings,
+    reviewers: &Reviewers,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+    cache: Option<&Cache>,
+) -> Result<(PathBuf, BTreeMap<VersionTag, AuthorMap>), Box<dyn std::error::Error>> {
+    let path = update_repo(&project.repository)?;
     let repo = git2::Repository::open(&path)?;
-    let mailmap = mailmap_from_repo(&repo)?;
-    let reviewers = Reviewers::new()?;
+    let fingerprint = cache::fingerprint(project, reviewers, bots, trailers);
 
-    let mut versions = get_versions(&repo)?;
+    let mut versions = get_versions(&repo, project)?;
     let last_full_stable = versions
         .iter()
         .rfind(|v| v.raw_tag.ends_with(".0"))
-        .unwrap()
+        .ok_or_else(|| {
+            ErrorMessage(format!(
+                "no release tags found for {} in {}",
+                project.name, project.repository
+            ))
+        })?
         .version
         .clone();
 
@@ -623,76 +997,146 @@ fn generate_thanks() -> Result<BTreeMap<VersionTag, AuthorMap>, Box<dyn std::err
         Err(_) => "main".to_string(),
     };
 
+    let mut next_version = last_full_stable.clone();
+    if let Some(beta_branch) = &project.tags.beta_branch {
+        next_version.minor += 1;
+        versions.push(VersionTag {
+            project: project.slug.clone(),
+            name: String::from("Beta"),
+            version: next_version.clone(),
+            raw_tag: beta_branch.clone(),
+            commit: resolve_commit(&repo, project, beta_branch)?,
+            in_progress: true,
+        });
+    }
     versions.push(VersionTag {
-        name: String::from("Beta"),
-        version: {
-            let mut last = last_full_stable.clone();
-            last.minor += 1;
-            last
-        },
-        raw_tag: String::from("beta"),
-        commit: repo
-            .revparse_single("beta")
-            .unwrap()
-            .peel_to_commit()
-            .unwrap()
-            .id(),
-        in_progress: true,
-    });
-    versions.push(VersionTag {
+        project: project.slug.clone(),
         name: String::from("Nightly"),
         version: {
-            // main is plus 1 minor versions off of beta, which we just pushed
-            let mut last = last_full_stable.clone();
-            last.minor += 2;
-            last
+            // main is plus 1 minor versions off of beta, if there is one
+            next_version.minor += 1;
+            next_version
         },
         raw_tag: nightly_branch,
-        commit: repo
-            .revparse_single("HEAD")
-            .unwrap()
-            .peel_to_commit()
-            .unwrap()
-            .id(),
+        commit: resolve_commit(&repo, project, "HEAD")?,
         in_progress: true,
     });
 
     let mut version_map = BTreeMap::new();
 
-    let mut cache = HashMap::new();
-
+    // Versions which aren't cached, and need to be walked from the previous version.
+    let mut pending = Vec::new();
     for (idx, version) in versions.iter().enumerate() {
-        let previous = if let Some(v) = idx.checked_sub(1).map(|idx| &versions[idx]) {
-            v
-        } else {
-            let author_map = build_author_map(&repo, &reviewers, &mailmap, "", &version.raw_tag)?;
+        // Versions still in progress change from run to run, so there's no
+        // point in caching them.
+        let cache = cache.filter(|_| !version.in_progress);
+        let previous = idx.checked_sub(1).map(|idx| versions[idx].commit);
+        if let Some(cached) = cache.and_then(|c| c.load(fingerprint, previous, version.commit)) {
+            version_map.insert(version.clone(), cached);
+        } else if previous.is_none() {
+            let author_map = build_author_map(
+                &repo,
+                reviewers,
+                bots,
+                trailers,
+                &project.reviewer_extractors,
+                &[],
+                &version.raw_tag,
+            )?;
+            if let Some(cache) = cache {
+                cache.store(fingerprint, None, version.commit, &author_map)?;
+            }
             version_map.insert(version.clone(), author_map);
-            continue;
-        };
+        } else {
+            pending.push(idx);
+        }
+    }
 
-        eprintln!("Processing {:?} to {:?}", previous, version);
+    // The versions which aren't cached are walked in parallel, each from the
+    // release before it.
+    let maps = pending
+        .into_par_iter()
+        .map_init(
+            || Repository::open(&path),
+            |repo, idx| {
+                let repo = repo.as_ref().map_err(|e| ErrorChain::new(e))?;
+                let (previous, version) = (&versions[idx - 1], &versions[idx]);
+                eprintln!("Processing {:?} to {:?}", previous, version);
+                let map =
+                    since_release(repo, project, reviewers, bots, trailers, previous, version)
+                        .map_err(ErrorChain::from)?;
+                Ok::<_, ErrorChain>((idx, map))
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    for (idx, map) in maps {
+        let (previous, version) = (&versions[idx - 1], &versions[idx]);
+        if let Some(cache) = cache.filter(|_| !version.in_progress) {
+            cache.store(fingerprint, Some(previous.commit), version.commit, &map)?;
+        }
+        version_map.insert(version.clone(), map);
+    }
 
-        cache.insert(
-            version,
-            up_to_release(&repo, &reviewers, &mailmap, version)?,
-        );
-        let previous = match cache.remove(&previous) {
-            Some(v) => v,
-            None => up_to_release(&repo, &reviewers, &mailmap, previous)?,
-        };
-        let current = cache.get(&version).unwrap();
+    Ok((path, version_map))
+}
 
-        // Remove commits reachable from the previous release.
-        let only_current = current.difference(&previous);
-        version_map.insert(version.clone(), only_current);
-    }
+/// Find the contributors to each release of a project, canonicalized with the
+/// mailmap revision configured for it and with the privacy exclusions applied.
+fn generate_thanks(
+    project: &ProjectSettings,
+    reviewers: &Reviewers,
+    bots: &BotSettings,
+    trailers: &TrailerSettings,
+    privacy: &Privacy,
+    cache: Option<&Cache>,
+) -> Result<BTreeMap<VersionTag, AuthorMap>, Box<dyn std::error::Error>> {
+    let (path, version_map) = walk_releases(project, reviewers, bots, trailers, cache)?;
+    let repo = Repository::open(&path)?;
+    let head = find_revision(&repo, "HEAD")?;
+    let fixed = match &project.mailmap_revision {
+        MailmapRevision::Head => Some(mailmap_at(&repo, project, &head, true)?),
+        MailmapRevision::Commit(revision) => {
+            let at = find_revision(&repo, revision)?;
+            Some(mailmap_at(&repo, project, &at, true)?)
+        }
+        MailmapRevision::Release => None,
+    };
 
-    Ok(version_map)
+    version_map
+        .into_iter()
+        .map(|(version, authors)| {
+            let release_mailmap;
+            let mailmap = match &fixed {
+                Some(mailmap) => mailmap,
+                None => {
+                    // Only the latest mailmap's problems can still be fixed.
+                    let at = repo.find_commit(version.commit)?;
+                    let warn = at.id() == head.id();
+                    release_mailmap = mailmap_at(&repo, project, &at, warn)?;
+                    &release_mailmap
+                }
+            };
+            let authors = privacy.apply(authors.canonicalize(mailmap), mailmap);
+            Ok((version, authors))
+        })
+        .collect()
 }
 
 enum OutputMode {
     Html,
     Csv,
+    Json,
+    /// Print a report of problems with the identity data instead of any output.
+    Audit,
+    /// Print draft mailmap entries for people with several identities.
+    SuggestMailmap,
+    /// Print the identities shown differently with the mailmaps of two revisions.
+    MailmapChanges {
+        from: String,
+        to: String,
+        /// The slug of the only project to compare, if not all of them.
+        project: Option<String>,
+    },
 }
 
 impl FromStr for OutputMode {
@@ -702,55 +1146,146 @@ impl FromStr for OutputMode {
         match s {
             "html" => Ok(Self::Html),
             "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "audit" => Ok(Self::Audit),
+            "suggest-mailmap" => Ok(Self::SuggestMailmap),
             _ => Err(format!(
-                "Invalid output mode {s}. Possible values: `html` or `csv`."
+                "Invalid output mode {s}. Possible values: `html`, `csv`, `json`, `audit`, \
+                 `suggest-mailmap` or `mailmap-changes <from> <to>`."
             )),
         }
     }
 }
 
 fn run(mode: OutputMode) -> Result<(), Box<dyn std::error::Error>> {
-    let by_version = generate_thanks()?;
-    let by_version: BTreeMap<_, _> = by_version
-        .into_iter()
-        .map(|(k, v)| (k, AuthorsWithScores::new(v)))
-        .collect();
+    let settings = Settings::load()?;
+    let reviewers = Reviewers::new(&settings.reviewers)?;
+    let privacy = Privacy::new(&settings.privacy, &reviewers)?;
+    let cache = settings.cache.as_ref().map(Cache::open).transpose()?;
+
+    if let OutputMode::MailmapChanges { from, to, project } = &mode {
+        let selected = match project {
+            Some(slug) => {
+                let project = settings.projects.iter().find(|p| p.slug == *slug);
+                vec![project.ok_or_else(|| ErrorMessage(format!("unknown project {}", slug)))?]
+            }
+            None => settings.projects.iter().collect(),
+        };
+        let several = selected.len() > 1;
+        let mut comparisons = Vec::new();
+        for project in selected {
+            let (path, by_version) = walk_releases(
+                project,
+                &reviewers,
+                &settings.bots,
+                &settings.trailers,
+                cache.as_ref(),
+            )?;
+            let repo = Repository::open(&path)?;
+            let mut authors = AuthorMap::new();
+            for version_authors in by_version.into_values() {
+                authors.extend(version_authors);
+            }
+            // A release tag or commit may only exist in the repository of one project.
+            let find = |revision| {
+                find_revision(&repo, revision).map_err(|e| {
+                    if !several {
+                        return e;
+                    }
+                    let hint = format!(
+                        "comparing {}, select a single project with `--project <slug>`",
+                        project.name
+                    );
+                    ErrorContext(hint, e).into()
+                })
+            };
+            comparisons.push(report::MailmapComparison {
+                project,
+                authors,
+                from: mailmap_at(&repo, project, &find(from)?, false)?,
+                to: mailmap_at(&repo, project, &find(to)?, false)?,
+            });
+        }
+        if let Some(cache) = &cache {
+            cache.prune()?;
+        }
+        let stdout = std::io::stdout().lock();
+        return report::mailmap_changes(from, to, &comparisons, &privacy, stdout);
+    }
+
+    let mut projects = Vec::new();
+    let mut all_time = AuthorMap::new();
+    for project in &settings.projects {
+        let by_version = generate_thanks(
+            project,
+            &reviewers,
+            &settings.bots,
+            &settings.trailers,
+            &privacy,
+            cache.as_ref(),
+        )?;
+        let by_version: BTreeMap<_, _> = by_version
+            .into_iter()
+            .map(|(k, v)| (k, AuthorsWithScores::new(v)))
+            .collect();
+
+        let mut project_all_time = AuthorMap::new();
+        for authors in by_version.values() {
+            project_all_time.extend(authors.authors.clone());
+        }
+        all_time.extend(project_all_time.clone());
 
-    let mut all_time = by_version.values().next().unwrap().authors.clone();
-    for authors in by_version.values().skip(1) {
-        all_time.extend(authors.authors.clone());
+        projects.push(ProjectThanks {
+            project,
+            by_version,
+            all_time: AuthorsWithScores::new(project_all_time),
+        });
     }
     let all_time = AuthorsWithScores::new(all_time);
+    if let Some(cache) = &cache {
+        cache.prune()?;
+    }
+    report::commit_problems(&projects, is_strict())?;
 
     match mode {
         OutputMode::Html => {
-            site::render(by_version, all_time)?;
+            site::render(&settings, &projects, all_time)?;
         }
         OutputMode::Csv => {
-            use std::io::Write;
-
-            let write = |path: &Path,
-                         authors: AuthorsWithScores|
-             -> Result<(), Box<dyn std::error::Error>> {
-                let mut file = BufWriter::new(std::fs::File::create(path)?);
-                for score in authors.scores {
-                    let AuthorScore {
-                        rank,
-                        author,
-                        email,
-                        commits,
-                    } = score;
-                    writeln!(file, "{rank},{author},{email},{commits}")?;
-                }
-                Ok(())
-            };
+            csv::write(&settings, &projects, &all_time)?;
+        }
+        OutputMode::Json => {
+            json::write(&settings, &projects, &all_time)?;
+        }
+        OutputMode::Audit => {
+            let stdout = std::io::stdout().lock();
+            return report::audit(&projects, &all_time, &reviewers, stdout);
+        }
+        OutputMode::SuggestMailmap => {
+            let stdout = std::io::stdout().lock();
+            return suggest::suggest_mailmap(&all_time, &reviewers, stdout);
+        }
+        OutputMode::MailmapChanges { .. } => unreachable!("handled before walking"),
+    }
 
-            let directory = PathBuf::from("output/csv");
-            std::fs::create_dir_all(&directory)?;
-            for (version, authors) in by_version {
-                write(&directory.join(format!("{version}.csv")), authors)?;
-            }
-            write(&directory.join("all-time.csv"), all_time)?;
+    let unknown = report::unknown_reviewers(&settings, &projects, &reviewers)?;
+    if unknown > 0 {
+        eprintln!(
+            "{unknown} unknown reviewers, see {}",
+            settings
+                .reports
+                .directory
+                .join("unknown-reviewers.json")
+                .display()
+        );
+    }
+    if settings.bots.action == BotAction::Report {
+        let bots = report::automation(&settings, &projects)?;
+        if bots > 0 {
+            eprintln!(
+                "{bots} bots left out of the rankings, see {}",
+                settings.reports.directory.join("automation.json").display()
+            );
         }
     }
 
@@ -758,11 +1293,28 @@ fn run(mode: OutputMode) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn main() {
-    let mode = std::env::args().nth(1);
-    let mode = match mode {
-        None => OutputMode::Html,
-        Some(mode) => mode.parse().unwrap(),
-    };
+    let mut mode = OutputMode::Html;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--offline" => OFFLINE.store(true, Ordering::Relaxed),
+            "--strict" => STRICT.store(true, Ordering::Relaxed),
+            "mailmap-changes" => {
+                let mut next = || {
+                    args.next()
+                        .expect("usage: thanks mailmap-changes [--project <slug>] <from> <to>")
+                };
+                let (mut from, mut project) = (next(), None);
+                if from == "--project" {
+                    project = Some(next());
+                    from = next();
+                }
+                let to = next();
+                mode = OutputMode::MailmapChanges { from, to, project };
+            }
+            _ => mode = arg.parse().unwrap(),
+        }
+    }
 
     if let Err(err) = run(mode) {
         eprintln!("Error: {}", err);
@@ -788,6 +1340,7 @@ struct Submodule {
 fn get_submodules(
     repo: &Repository,
     at: &Commit,
+    settings: &SubmoduleSettings,
 ) -> Result<Vec<Submodule>, Box<dyn std::error::Error>> {
     let submodule_cfg = modules_file(repo, at)?;
     let submodule_cfg = Config::parse(&submodule_cfg)?;
@@ -818,28 +1371,7 @@ fn get_submodules(
             repository: url.to_owned(),
         });
     }
-    submodules.retain(|s| {
-        let is_rust =
-            s.repository.contains("rust-lang") || s.repository.contains("rust-lang-nursery");
-        let exclude = vec![
-            "https://github.com/rust-lang/llvm.git",
-            "https://github.com/rust-lang/llvm-project.git",
-            "https://github.com/rust-lang/lld.git",
-            "https://github.com/rust-lang/enzyme.git",
-            "https://github.com/rust-lang-nursery/clang.git",
-            "https://github.com/rust-lang-nursery/lldb.git",
-            "https://github.com/rust-lang/libuv.git",
-            "https://github.com/rust-lang/gyp.git",
-            "https://github.com/rust-lang/jemalloc.git",
-            "https://github.com/rust-lang/compiler-rt.git",
-            "https://github.com/rust-lang/hoedown.git",
-            "https://github.com/rust-lang/gcc.git",
-        ];
-        let repo_name = s.repository.to_lowercase();
-        is_rust
-            && !exclude.contains(&repo_name.as_str())
-            && !exclude.contains(&&*format!("{}.git", repo_name))
-    });
+    submodules.retain(|s| settings.matches(&s.repository));
 
     // Sort the submodules to ensure deterministic commit iteration order
     submodules.sort_by(|a, b| a.repository.cmp(&b.repository));
@@ -0,0 +1,228 @@
+//! Leaving people who asked for it off the site.
+//!
+//! Everyone in `[privacy]` in `thanks.toml` is either dropped, in which case
+//! their contributions aren't counted anywhere, or shown as an anonymous
+//! contributor, in which case they still count towards every total and rank
+//! but their name and email are never written out. Either way, this happens
+//! before scoring, so nothing downstream ever sees them.
+
+use crate::AuthorMap;
+use crate::error::ErrorMessage;
+use crate::reviewers::Reviewers;
+use crate::settings::{ExclusionAction, PrivacySettings};
+use mailmap::{Author, Mailmap};
+use unicase::UniCase;
+
+/// The name anonymized people are shown under.
+const ANONYMOUS_NAME: &str = "Anonymous contributor";
+
+/// The domain of the emails standing in for anonymized people's, which is
+/// reserved so that it can't be anyone's actual email.
+const PLACEHOLDER_DOMAIN: &str = "anonymous.invalid";
+
+/// Whether `author` stands in for an anonymized person. Placeholders all share
+/// a name, so they must not be taken as the same person under several emails.
+pub fn is_placeholder(author: &Author) -> bool {
+    author
+        .email
+        .rsplit_once('@')
+        .is_some_and(|(_, domain)| domain == PLACEHOLDER_DOMAIN)
+}
+
+enum Identity {
+    /// A canonical email.
+    Email(UniCase<String>),
+    /// A reviewer, whose canonical email depends on the mailmap.
+    Reviewer(Author),
+}
+
+struct Excluded {
+    identity: Identity,
+    action: ExclusionAction,
+    /// Stands in for the person's email when anonymized.
+    placeholder: Author,
+}
+
+impl Excluded {
+    /// The canonical email of the person with `mailmap`.
+    fn email(&self, mailmap: &Mailmap) -> UniCase<String> {
+        match &self.identity {
+            Identity::Email(email) => email.clone(),
+            Identity::Reviewer(author) => mailmap.canonicalize(author).email,
+        }
+    }
+}
+
+pub struct Privacy {
+    excluded: Vec<Excluded>,
+}
+
+impl Privacy {
+    /// Resolve the people to exclude, failing if a username is unknown, as
+    /// they would otherwise silently be shown.
+    pub fn new(
+        settings: &PrivacySettings,
+        reviewers: &Reviewers,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut excluded = Vec::new();
+        for exclusion in &settings.exclude {
+            let identity = match (&exclusion.email, &exclusion.username) {
+                (Some(email), _) => Identity::Email(UniCase::new(email.clone())),
+                (None, Some(username)) => match reviewers.to_author(username) {
+                    Ok(Some(author)) => Identity::Reviewer(author),
+                    Ok(None) | Err(_) => {
+                        return Err(ErrorMessage(format!(
+                            "cannot exclude unknown reviewer {} for privacy",
+                            username
+                        ))
+                        .into());
+                    }
+                },
+                (None, None) => unreachable!("checked when loading the settings"),
+            };
+            // Named after the exclusion's id so that the same person keeps the
+            // same profile from run to run, without deriving anything from them.
+            let id = exclusion.id.as_deref().unwrap_or_default();
+            excluded.push(Excluded {
+                identity,
+                action: exclusion.action,
+                placeholder: Author::new(
+                    ANONYMOUS_NAME.into(),
+                    format!("{}@{}", id, PLACEHOLDER_DOMAIN),
+                ),
+            });
+        }
+        Ok(Privacy { excluded })
+    }
+
+    /// Whether someone canonicalized with `mailmap` is dropped or anonymized.
+    pub fn excludes(&self, author: &Author, mailmap: &Mailmap) -> bool {
+        self.excluded
+            .iter()
+            .any(|excluded| excluded.email(mailmap) == author.email)
+    }
+
+    /// Drop or anonymize the excluded people in a map canonicalized with `mailmap`.
+    #[must_use]
+    pub fn apply(&self, map: AuthorMap, mailmap: &Mailmap) -> AuthorMap {
+        if self.excluded.is_empty() {
+            return map;
+        }
+        let emails = self
+            .excluded
+            .iter()
+            .map(|excluded| excluded.email(mailmap))
+            .collect::<Vec<_>>();
+
+        map.map_authors(
+            |author| match emails.iter().position(|email| *email == author.email) {
+                None => Some(author),
+                Some(idx) => match self.excluded[idx].action {
+                    ExclusionAction::Drop => None,
+                    ExclusionAction::Anonymize => Some(self.excluded[idx].placeholder.clone()),
+                },
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ContributionKind;
+    use crate::reviewers::ReviewerData;
+    use crate::settings::Exclusion;
+    use git2::Oid;
+
+    fn exclusion(
+        email: Option<&str>,
+        username: Option<&str>,
+        action: ExclusionAction,
+        id: Option<&str>,
+    ) -> Exclusion {
+        Exclusion {
+            email: email.map(String::from),
+            username: username.map(String::from),
+            action,
+            id: id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn excludes_people() {
+        let data = ReviewerData::from_str(
+            r#"
+            version = 1
+            [reviewers]
+            someone = { name = "Some One", email = "someone@old.example" }
+            "#,
+        )
+        .unwrap();
+        let reviewers = Reviewers::from_data(None, data, false).unwrap();
+        let settings = PrivacySettings {
+            exclude: vec![
+                exclusion(
+                    Some("Dropped@example.com"),
+                    None,
+                    ExclusionAction::Drop,
+                    None,
+                ),
+                exclusion(
+                    None,
+                    Some("someone"),
+                    ExclusionAction::Anonymize,
+                    Some("a1"),
+                ),
+            ],
+        };
+        let privacy = Privacy::new(&settings, &reviewers).unwrap();
+        let mailmap =
+            Mailmap::from_string("Some One <someone@example.com> <someone@old.example>".into())
+                .unwrap();
+
+        let author = |name: &str, email: &str| Author::new(name.into(), email.into());
+        let mut map = AuthorMap::new();
+        let oid = |n: u8| Oid::from_bytes(&[n; 20]).unwrap();
+        map.add(
+            author("Dropped", "dropped@example.com"),
+            oid(1),
+            ContributionKind::Authored,
+        );
+        map.add(
+            author("Some One", "someone@example.com"),
+            oid(2),
+            ContributionKind::Authored,
+        );
+        map.add(
+            author("Some One", "someone@example.com"),
+            oid(3),
+            ContributionKind::Reviewed,
+        );
+        map.add(
+            author("Kept", "kept@example.com"),
+            oid(4),
+            ContributionKind::Authored,
+        );
+
+        let map = privacy.apply(map, &mailmap);
+        let mut authors = map
+            .iter()
+            .map(|(author, commits)| (author.name.to_string(), author.email.to_string(), commits))
+            .collect::<Vec<_>>();
+        authors.sort();
+        assert_eq!(
+            authors,
+            [
+                (ANONYMOUS_NAME.into(), "a1@anonymous.invalid".into(), 2),
+                ("Kept".into(), "kept@example.com".into(), 1),
+            ]
+        );
+        assert!(map.iter().any(|(author, _)| is_placeholder(author)));
+        assert!(!is_placeholder(&author("Kept", "kept@example.com")));
+
+        let unknown = PrivacySettings {
+            exclude: vec![exclusion(None, Some("nobody"), ExclusionAction::Drop, None)],
+        };
+        assert!(Privacy::new(&unknown, &reviewers).is_err());
+    }
+}
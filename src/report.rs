@@ -0,0 +1,503 @@
+//! Reports on problems in the input data, for maintainers to act on.
+//!
+//! `unknown-reviewers.json` is written to the directory set in `[reports]`
+//! (`reports/` by default, outside the site so it isn't published) whatever the
+//! output mode. It lists every reviewer named in a merge commit who couldn't be
+//! resolved to anyone, with the commits naming them and the known usernames they
+//! are most likely a typo of. Entries for these belong in the `[aliases]` or
+//! `[reviewers]` sections of `reviewers.toml`.
+//!
+//! With `action = "report"` in `[bots]`, `automation.json` is written next to
+//! it, listing the bots left out of the rankings and the commits crediting them.
+//!
+//! The `audit` command prints a superset of these to standard output, adding
+//! the identities which likely need `.mailmap` entries (see [`audit`]).
+//!
+//! The `mailmap-changes` command prints the identities whose canonical form
+//! differs between two revisions of the mailmaps (see [`mailmap_changes`]).
+//!
+//! Like the JSON output, every report carries a `schema_version`.
+
+use crate::error::ErrorMessage;
+use crate::privacy::{Privacy, is_placeholder};
+use crate::reviewers::Reviewers;
+use crate::settings::{ProjectSettings, Settings};
+use crate::{AuthorMap, AuthorsWithScores, Occurrences, ProjectThanks};
+use mailmap::{Author, Mailmap};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufWriter, Write};
+use unicase::UniCase;
+
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct UnknownReviewers<'a> {
+    schema_version: u32,
+    unknown_reviewers: Vec<UnknownReviewer<'a>>,
+}
+
+#[derive(Serialize)]
+struct UnknownReviewer<'a> {
+    /// The name as it appears after `r=`, lowercased.
+    reviewer: &'a str,
+    count: usize,
+    commits: Vec<Commit<'a>>,
+    suggestions: Vec<Suggestion<'a>>,
+}
+
+/// A name found in commits, as it appears in them.
+#[derive(Serialize)]
+struct Occurrence<'a> {
+    name: &'a str,
+    count: usize,
+    commits: Vec<Commit<'a>>,
+}
+
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+struct Commit<'a> {
+    project: &'a str,
+    commit: String,
+}
+
+#[derive(Serialize)]
+struct Suggestion<'a> {
+    username: &'a str,
+    name: &'a str,
+    email: &'a str,
+    distance: usize,
+}
+
+#[derive(Serialize)]
+struct Automation<'a> {
+    schema_version: u32,
+    automation: Vec<Occurrence<'a>>,
+}
+
+#[derive(Serialize)]
+struct Audit<'a> {
+    schema_version: u32,
+    unknown_reviewers: Vec<UnknownReviewer<'a>>,
+    malformed_reviewers: Vec<Occurrence<'a>>,
+    automation: Vec<Occurrence<'a>>,
+    commit_problems: Vec<Occurrence<'a>>,
+    names_with_several_emails: Vec<SharedName<'a>>,
+    emails_with_several_names: Vec<SharedEmail<'a>>,
+}
+
+/// Canonical identities with the same name but different emails, which are
+/// counted as different people.
+#[derive(Serialize)]
+struct SharedName<'a> {
+    name: &'a str,
+    emails: Vec<Identity<'a>>,
+}
+
+/// Canonical identities with the same email but different names, which are
+/// merged into one person shown under a single name.
+#[derive(Serialize)]
+struct SharedEmail<'a> {
+    email: &'a str,
+    /// The name the merged person is shown under.
+    shown_as: &'a str,
+    names: Vec<Identity<'a>>,
+}
+
+/// One identity of a person, and the number of commits credited to it.
+#[derive(Serialize)]
+struct Identity<'a> {
+    name: &'a str,
+    email: &'a str,
+    commits: usize,
+}
+
+/// Gather the occurrences selected by `select` across all projects, by name.
+fn occurrences<'a>(
+    projects: &'a [ProjectThanks<'_>],
+    select: fn(&AuthorMap) -> &Occurrences,
+) -> BTreeMap<&'a str, Vec<Commit<'a>>> {
+    let mut found: BTreeMap<&str, Vec<Commit<'_>>> = BTreeMap::new();
+    for project in projects {
+        for (name, oids) in select(&project.all_time.authors).iter() {
+            found
+                .entry(name)
+                .or_default()
+                .extend(oids.iter().map(|oid| Commit {
+                    project: &project.project.slug,
+                    commit: oid.to_string(),
+                }));
+        }
+    }
+    for commits in found.values_mut() {
+        commits.sort();
+    }
+    found
+}
+
+/// The occurrences selected by `select`, the most common ones first.
+fn occurrence_entries<'a>(
+    projects: &'a [ProjectThanks<'_>],
+    select: fn(&AuthorMap) -> &Occurrences,
+) -> Vec<Occurrence<'a>> {
+    let mut found = occurrences(projects, select)
+        .into_iter()
+        .map(|(name, commits)| Occurrence {
+            name,
+            count: commits.len(),
+            commits,
+        })
+        .collect::<Vec<_>>();
+    found.sort_by_key(|o| std::cmp::Reverse(o.count));
+    found
+}
+
+fn unknown_reviewer_entries<'a>(
+    projects: &'a [ProjectThanks<'_>],
+    reviewers: &'a Reviewers,
+) -> Vec<UnknownReviewer<'a>> {
+    let mut unknown = occurrences(projects, |map| &map.unknown_reviewers)
+        .into_iter()
+        .map(|(reviewer, commits)| {
+            let suggestions = reviewers
+                .suggest(reviewer)
+                .into_iter()
+                .map(|s| Suggestion {
+                    username: s.username,
+                    name: &s.author.name,
+                    email: &s.author.email,
+                    distance: s.distance,
+                })
+                .collect();
+            UnknownReviewer {
+                reviewer,
+                count: commits.len(),
+                commits,
+                suggestions,
+            }
+        })
+        .collect::<Vec<_>>();
+    // The most common ones first, as those are the most worthwhile to fix.
+    unknown.sort_by_key(|u| std::cmp::Reverse(u.count));
+    unknown
+}
+
+/// Write `unknown-reviewers.json`, returning the number of unknown reviewers.
+pub fn unknown_reviewers(
+    settings: &Settings,
+    projects: &[ProjectThanks<'_>],
+    reviewers: &Reviewers,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let unknown = unknown_reviewer_entries(projects, reviewers);
+
+    let directory = &settings.reports.directory;
+    fs::create_dir_all(directory)?;
+    let file = BufWriter::new(fs::File::create(directory.join("unknown-reviewers.json"))?);
+    let count = unknown.len();
+    serde_json::to_writer_pretty(
+        file,
+        &UnknownReviewers {
+            schema_version: SCHEMA_VERSION,
+            unknown_reviewers: unknown,
+        },
+    )?;
+    Ok(count)
+}
+
+/// Write `automation.json`, returning the number of bots in it.
+pub fn automation(
+    settings: &Settings,
+    projects: &[ProjectThanks<'_>],
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let automation = occurrence_entries(projects, |map| &map.automation);
+
+    let directory = &settings.reports.directory;
+    fs::create_dir_all(directory)?;
+    let file = BufWriter::new(fs::File::create(directory.join("automation.json"))?);
+    let count = automation.len();
+    serde_json::to_writer_pretty(
+        file,
+        &Automation {
+            schema_version: SCHEMA_VERSION,
+            automation,
+        },
+    )?;
+    Ok(count)
+}
+
+/// Warn about the commits which couldn't be fully read, or fail if `strict`.
+pub fn commit_problems(
+    projects: &[ProjectThanks<'_>],
+    strict: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let problems = occurrence_entries(projects, |map| &map.problems);
+    for problem in &problems {
+        let first = &problem.commits[0];
+        let message = format!(
+            "{} in {} commits, e.g. {} of {}",
+            problem.name, problem.count, first.commit, first.project
+        );
+        if strict {
+            return Err(ErrorMessage(format!(
+                "{}; rerun without --strict to skip over these",
+                message
+            ))
+            .into());
+        }
+        eprintln!("warning: {}", message);
+    }
+    Ok(())
+}
+
+/// Write a report of everything that looks wrong with the identity data.
+///
+/// Besides the unknown reviewers, this lists reviewer names with characters
+/// which can't appear in a username, the reported bots, the commits which
+/// couldn't be fully read, and the canonical identities (after
+/// applying the mailmap) sharing a name or an email with another one. Either
+/// is usually someone who committed under several identities, and needs a
+/// `.mailmap` entry to be counted as one person under their preferred name.
+pub fn audit(
+    projects: &[ProjectThanks<'_>],
+    all_time: &AuthorsWithScores,
+    reviewers: &Reviewers,
+    out: impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut by_name: BTreeMap<&UniCase<String>, Vec<Identity<'_>>> = BTreeMap::new();
+    let mut by_email: BTreeMap<String, Vec<Identity<'_>>> = BTreeMap::new();
+    for (author, contributions) in all_time.authors.contributions() {
+        // Anonymized people all share one name, and aren't to be looked into.
+        if is_placeholder(author) {
+            continue;
+        }
+        let identity = || Identity {
+            name: &author.name,
+            email: &author.email,
+            commits: contributions.commits,
+        };
+        by_name.entry(&author.name).or_default().push(identity());
+        // The same key `score::deduplicate_scores` merges people by.
+        by_email
+            .entry(author.email.to_lowercase())
+            .or_default()
+            .push(identity());
+    }
+    let by_commits = |identities: &mut Vec<Identity<'_>>| {
+        identities.sort_by_key(|i| std::cmp::Reverse(i.commits))
+    };
+
+    let mut shared_names = by_name
+        .into_iter()
+        .filter(|(_, identities)| identities.len() > 1)
+        .map(|(_, mut emails)| {
+            by_commits(&mut emails);
+            SharedName {
+                name: emails[0].name,
+                emails,
+            }
+        })
+        .collect::<Vec<_>>();
+    shared_names
+        .sort_by_key(|s| std::cmp::Reverse(s.emails.iter().map(|i| i.commits).sum::<usize>()));
+
+    let shown_as: HashMap<String, &str> = all_time
+        .scores
+        .iter()
+        .map(|score| (score.email.to_lowercase(), score.author.as_str()))
+        .collect();
+    let mut shared_emails = by_email
+        .into_iter()
+        .filter(|(_, identities)| identities.len() > 1)
+        .map(|(email, mut names)| {
+            by_commits(&mut names);
+            SharedEmail {
+                email: names[0].email,
+                shown_as: shown_as.get(&email).copied().unwrap_or(names[0].name),
+                names,
+            }
+        })
+        .collect::<Vec<_>>();
+    shared_emails
+        .sort_by_key(|s| std::cmp::Reverse(s.names.iter().map(|i| i.commits).sum::<usize>()));
+
+    serde_json::to_writer_pretty(
+        out,
+        &Audit {
+            schema_version: SCHEMA_VERSION,
+            unknown_reviewers: unknown_reviewer_entries(projects, reviewers),
+            malformed_reviewers: occurrence_entries(projects, |map| &map.malformed_reviewers),
+            automation: occurrence_entries(projects, |map| &map.automation),
+            commit_problems: occurrence_entries(projects, |map| &map.problems),
+            names_with_several_emails: shared_names,
+            emails_with_several_names: shared_emails,
+        },
+    )?;
+    Ok(())
+}
+
+/// The identities found in a project's commits, before any mailmap is
+/// applied, along with the mailmaps to compare.
+pub struct MailmapComparison<'a> {
+    pub project: &'a ProjectSettings,
+    pub authors: AuthorMap,
+    pub from: Mailmap,
+    pub to: Mailmap,
+}
+
+#[derive(Serialize)]
+struct MailmapChanges<'a> {
+    schema_version: u32,
+    from: &'a str,
+    to: &'a str,
+    changes: Vec<MailmapChange<'a>>,
+}
+
+/// An identity found in commits which is shown differently with each mailmap.
+#[derive(Serialize)]
+struct MailmapChange<'a> {
+    project: &'a str,
+    name: &'a str,
+    email: &'a str,
+    commits: usize,
+    from: Canonical,
+    to: Canonical,
+}
+
+#[derive(Serialize)]
+struct Canonical {
+    name: String,
+    email: String,
+}
+
+impl From<Author> for Canonical {
+    fn from(author: Author) -> Self {
+        Canonical {
+            name: author.name.into_inner(),
+            email: author.email.into_inner(),
+        }
+    }
+}
+
+/// Write a report of every identity whose canonical name or email differs
+/// between the `from` and `to` mailmaps of its project.
+///
+/// This explains why someone is shown differently on two pages, e.g. an old
+/// release page generated with the mailmap of that release. People excluded by
+/// `privacy` with either mailmap are left out, as the report would otherwise
+/// name them.
+pub fn mailmap_changes(
+    from: &str,
+    to: &str,
+    projects: &[MailmapComparison<'_>],
+    privacy: &Privacy,
+    out: impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut changes = Vec::new();
+    for comparison in projects {
+        for (author, commits) in comparison.authors.iter() {
+            let (before, after) = (
+                comparison.from.canonicalize(author),
+                comparison.to.canonicalize(author),
+            );
+            // Compare exactly, as a change in case alone is still visible.
+            if before.name.as_str() == after.name.as_str()
+                && before.email.as_str() == after.email.as_str()
+            {
+                continue;
+            }
+            if privacy.excludes(&before, &comparison.from)
+                || privacy.excludes(&after, &comparison.to)
+            {
+                continue;
+            }
+            changes.push(MailmapChange {
+                project: &comparison.project.slug,
+                name: &author.name,
+                email: &author.email,
+                commits,
+                from: before.into(),
+                to: after.into(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then_with(|| (a.project, a.name, a.email).cmp(&(b.project, b.name, b.email)))
+    });
+
+    serde_json::to_writer_pretty(
+        out,
+        &MailmapChanges {
+            schema_version: SCHEMA_VERSION,
+            from,
+            to,
+            changes,
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ContributionKind;
+    use crate::reviewers::ReviewerData;
+    use crate::settings::{Exclusion, ExclusionAction, PrivacySettings};
+    use git2::Oid;
+
+    #[test]
+    fn mailmap_changes_leave_out_excluded() {
+        let project: ProjectSettings = toml::from_str(
+            r#"
+            name = "Rust"
+            slug = "rust"
+            repository = "https://example.com/rust.git"
+            "#,
+        )
+        .unwrap();
+        let reviewers =
+            Reviewers::from_data(None, ReviewerData::from_str("version = 1").unwrap(), false)
+                .unwrap();
+        let settings = PrivacySettings {
+            exclude: vec![Exclusion {
+                email: Some("hidden@example.com".into()),
+                username: None,
+                action: ExclusionAction::Anonymize,
+                id: Some("a1".into()),
+            }],
+        };
+        let privacy = Privacy::new(&settings, &reviewers).unwrap();
+
+        let mut authors = AuthorMap::new();
+        let author = |name: &str, email: &str| Author::new(name.into(), email.into());
+        let oid = |n: u8| Oid::from_bytes(&[n; 20]).unwrap();
+        authors.add(
+            author("Hidden", "hidden@old.example"),
+            oid(1),
+            ContributionKind::Authored,
+        );
+        authors.add(
+            author("Shown", "shown@old.example"),
+            oid(2),
+            ContributionKind::Authored,
+        );
+        let comparison = MailmapComparison {
+            project: &project,
+            authors,
+            from: Mailmap::from_string(String::new()).unwrap(),
+            to: Mailmap::from_string(
+                "<hidden@example.com> <hidden@old.example>\n\
+                 <shown@example.com> <shown@old.example>\n"
+                    .into(),
+            )
+            .unwrap(),
+        };
+
+        let mut out = Vec::new();
+        mailmap_changes("v1", "v2", &[comparison], &privacy, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("shown@example.com"), "{}", out);
+        assert!(!out.contains("hidden"), "{}", out);
+    }
+}
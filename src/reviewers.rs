@@ -1,231 +1,201 @@
+use crate::error::{ErrorContext, ErrorMessage};
+use crate::hash::StableHasher;
+use crate::settings::ReviewerSettings;
 use mailmap::Author;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long to wait for the team API before falling back to the snapshot.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The reviewer data used when `reviewers.data` doesn't name a file.
+const DEFAULT_DATA: &str = include_str!("../reviewers.toml");
+
+/// The format version of the reviewer data file understood by this build.
+const DATA_VERSION: u32 = 1;
+
+/// Reviewers which aren't in rust-lang/team, read from `reviewers.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReviewerData {
+    version: u32,
+    /// Tokens following `r=` which don't name a reviewer.
+    #[serde(default)]
+    ignore: Vec<String>,
+    /// People missing from the team data, by username.
+    #[serde(default)]
+    reviewers: BTreeMap<String, DataReviewer>,
+    /// Alternative usernames, mapped to the username they refer to.
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DataReviewer {
+    name: String,
+    email: String,
+}
+
+impl ReviewerData {
+    /// Load the data file named in the settings, or the built-in one.
+    pub fn load(settings: &ReviewerSettings) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = &settings.data else {
+            return ReviewerData::from_str(DEFAULT_DATA);
+        };
+        let parse = || -> Result<Self, Box<dyn Error>> {
+            ReviewerData::from_str(&std::fs::read_to_string(path)?)
+        };
+        parse().map_err(|e| {
+            ErrorContext(format!("loading reviewers from {}", path.display()), e).into()
+        })
+    }
+
+    pub fn from_str(s: &str) -> Result<Self, Box<dyn Error>> {
+        let data: ReviewerData = toml::from_str(s)?;
+        if data.version != DATA_VERSION {
+            return Err(ErrorMessage(format!(
+                "unsupported reviewer data version {} (expected {DATA_VERSION})",
+                data.version
+            ))
+            .into());
+        }
+
+        // Usernames are looked up case-insensitively, so each may only appear once
+        // across all sections, whatever its case.
+        let mut seen = HashSet::new();
+        let keys = data
+            .ignore
+            .iter()
+            .chain(data.reviewers.keys())
+            .chain(data.aliases.keys());
+        for key in keys {
+            if !seen.insert(key.to_lowercase()) {
+                return Err(ErrorMessage(format!("{key:?} is listed more than once")).into());
+            }
+        }
+        for (alias, target) in &data.aliases {
+            let target = target.to_lowercase();
+            if data.aliases.keys().any(|a| a.to_lowercase() == target) {
+                return Err(ErrorMessage(format!(
+                    "alias {alias:?} points at another alias, {target:?}"
+                ))
+                .into());
+            }
+            if data.ignore.iter().any(|i| i.to_lowercase() == target) {
+                return Err(ErrorMessage(format!(
+                    "alias {alias:?} points at ignored token {target:?}"
+                ))
+                .into());
+            }
+        }
+        Ok(data)
+    }
+}
 
 pub struct Reviewers {
     reviewers: HashMap<String, Author>,
+    ignore: HashSet<String>,
 }
 
 impl Reviewers {
-    #[rustfmt::skip]
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut map: HashMap<String, Author> = HashMap::new();
-        // FIXME: somehow dynamically generate this list. For now, it's small enough that
-        // maintaining it here is not too much of a hardship.
+    pub fn new(settings: &ReviewerSettings) -> Result<Self, Box<dyn std::error::Error>> {
+        Reviewers::from_data(
+            load_team_people(settings)?,
+            ReviewerData::load(settings)?,
+            crate::is_strict(),
+        )
+    }
 
-        enum AddKind {
-            New(Author),
-            Alias(&'static str),
-        }
+    /// Build the reviewer table from already loaded team data, if any, and the
+    /// reviewer data file.
+    ///
+    /// Aliases pointing at nobody, and entries conflicting with the team data,
+    /// are skipped with a warning rather than being an error, so that someone
+    /// leaving or joining the team data doesn't break every run. With `strict`
+    /// they are errors.
+    pub fn from_data(
+        team_people: Option<TeamPeople>,
+        data: ReviewerData,
+        strict: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let problem = |message: String| -> Result<(), Box<dyn Error>> {
+            if strict {
+                return Err(ErrorMessage(message).into());
+            }
+            eprintln!("WARNING: {message}");
+            Ok(())
+        };
+        let mut map: HashMap<String, Author> = HashMap::new();
 
-        fn a(name: &str, email: &str) -> AddKind {
-            AddKind::New(Author {
-                name: name.into(),
-                email: email.into(),
-            })
+        if let Some(team_people) = team_people {
+            for (username, person) in team_people.people {
+                if let Some(email) = person.email {
+                    map.insert(username.to_lowercase(), Author::new(person.name, email));
+                }
+            }
         }
 
-        fn alias(name: &'static str) -> AddKind {
-            AddKind::Alias(name)
+        for (name, reviewer) in data.reviewers {
+            let name = name.to_lowercase();
+            if map
+                .insert(name.clone(), Author::new(reviewer.name, reviewer.email))
+                .is_some()
+            {
+                problem(format!(
+                    "Reviewer is already pulled in from rust-lang/team: {name}"
+                ))?;
+            }
         }
 
-        let team_people = get_team_people()?;
-        for (username, person) in team_people.people {
-            if let Some(email) = person.email {
-                map.insert(username.to_lowercase(), Author::new(person.name, email));
+        for (name, aliased) in data.aliases {
+            let name = name.to_lowercase();
+            let Some(author) = map.get(&aliased.to_lowercase()).cloned() else {
+                problem(format!(
+                    "Trying to alias reviewer that doesn't exist: {aliased}"
+                ))?;
+                continue;
+            };
+            if map.insert(name.clone(), author).is_some() {
+                problem(format!(
+                    "Alias shadows a reviewer from rust-lang/team: {name}"
+                ))?;
             }
         }
 
-        let mut insert = |name: &str, author: AddKind| {
-            match author {
-                AddKind::New(author) => if map.insert(name.into(), author).is_some() {
-                    eprintln!("WARNING: Reviewer is already pulled in from rust-lang/team: {name}");
-                },
-                AddKind::Alias(aliased) => {
-                    if let Some(author) = map.get(aliased).cloned() {
-                        map.insert(name.into(), author);
-                    } else {
-                        eprintln!("WARNING: Trying to alias reviewer that doesn't exist: {aliased}")
-                    }
-                }
-            }
-        };
+        let ignore = data.ignore.iter().map(|i| i.to_lowercase()).collect();
+        Ok(Reviewers {
+            reviewers: map,
+            ignore,
+        })
+    }
 
-        // Additional reviewers which are not present in rust-lang/team
-        insert("aaronepower", a("Erin Power", "xampprocky@gmail.com"));
-        insert("abonander", a("Austin Bonander", "austin.bonander@gmail.com"));
-        insert("aleksator", a("Alex Tokarev", "aleksator@gmail.com"));
-        insert("alexreg", a("Alexander Regueiro", "alexreg@me.com"));
-        insert("anasazi", a("Eric Reed", "ecreed@cs.washington.edu"));
-        insert("apasel422", a("Andrew Paseltiner", "apaseltiner@gmail.com"));
-        insert("arthurprs", a("arthurprs", "arthurprs@gmail.com"));
-        insert("bblum", a("Ben Blum", "bblum@andrew.cmu.edu"));
-        insert("bjz", a("Brendan Zabarauskas", "bjzaba@yahoo.com.au"));
-        insert("bluss", a("Ulrik Sverdrup", "bluss@users.noreply.github.com"));
-        insert("brson", a("Brian Anderson", "andersrb@gmail.com"));
-        insert("bugadani", a("Dániel Buga", "bugadani@gmail.com"));
-        insert("c410-f3r", a("Caio", "c410.f3r@gmail.com"));
-        insert("camelid", a("Noah Lev", "camelidcamel@gmail.com"));
-        insert("catamorphism", a("Tim Chevalier", "chevalier@alum.wellesley.edu"));
-        insert("cdirkx", a("Christiaan Dirkx", "christiaan@dirkx.email"));
-        insert("chris", a("Chris Morgan", "me@chrismorgan.info"));
-        insert("cldfire", a("Jarek Samic", "cldfire3@gmail.com"));
-        insert("cmr", a("Corey Richardson", "corey@octayn.net"));
-        insert("collin5", a("Collins Abitekaniza", "abtcolns@gmail.com"));
-        insert("csmoe", a("csmoe", "csmoe@msn.com"));
-        insert("dingelish", a("Yu Ding", "dingelish@gmail.com"));
-        insert("dns2utf8", a("Stefan Schindler", "dns2utf8@estada.ch"));
-        insert("durka", a("Alex Durka", "web@alexburka.com"));
-        insert("eerden", a("Ercan Erden", "ercerden@gmail.com"));
-        insert("elichai", a("Elichai Turkel", "elichai.turkel@gmail.com"));
-        insert("euclio", a("Andy Russell", "arussell123@gmail.com"));
-        insert("flaper87", a("Flavio Percoco", "flaper87@gmail.com"));
-        insert("gereeter", a("Jonathan S", "gereeter+code@gmail.com"));
-        insert("gnzlbg", a("gnzlbg", "gonzalobg88@gmail.com"));
-        insert("graydon", a("Graydon Hoare", "graydon@pobox.com"));
-        insert("hanna-kruppe", a("Hanna Kruppe", "hanna.kruppe@gmail.com"));
-        insert("hellow554", a("Marcel Hellwig", "github@cookiesoft.de"));
-        insert("ilyoan", a("ILYONG CHO", "ilyoan@gmail.com"));
-        insert("jakub", a("Jakub Kądziołka", "kuba@kadziolka.net"));
-        insert("jbclements", a("John Clements", "clements@racket-lang.org"));
-        insert("jdm", a("Josh Matthews", "josh@joshmatthews.net"));
-        insert("jethrogb", a("Jethro Beekman", "jethro@fortanix.com"));
-        insert("jonas-schievink", a("Ghost", "ghost"));
-        insert("jroesch", a("Jared Roesch", "roeschinc@gmail.com"));
-        insert("jsgf", a("Jeremy Fitzhardinge", "jsgf@fb.com"));
-        insert("kballard", a("Lily Ballard", "lily@sb.org"));
-        insert("keeperofdakeys", a("Josh Driver", "keeperofdakeys@gmail.com"));
-        insert("kmcallister", a("Keegan McAllister", "mcallister.keegan@gmail.com"));
-        insert("kornelski", a("Kornel", "kornel@geekhood.net"));
-        insert("lingman", a("LingMan", "LingMan@users.noreply.github.com"));
-        insert("ljedrz", a("ljedrz", "ljedrz@gmail.com"));
-        insert("lukaramu", a("lukaramu", "lukaramu@users.noreply.github.com"));
-        insert("lzutao", a("Lzu Tao", "taolzu@gmail.com"));
-        insert("malbarbo", a("Marco A L Barbosa", "malbarbo@gmail.com"));
-        insert("marmeladema", a("marmeladema", "xademax@gmail.com"));
-        insert("max-heller", a("max-heller", "max.a.heller@gmail.com"));
-        insert("metajack", a("Jack Moffitt", "jack@metajack.im"));
-        insert("mikerite", a("Michael Wright", "mikerite@lavabit.com"));
-        insert("mikeyhew", a("Michael Hewson", "michael@michaelhewson.ca"));
-        insert("mjbshaw", a("Michael Bradshaw", "mjbshaw@google.com"));
-        insert("msullivan", a("Michael J. Sullivan", "sully@msully.net"));
-        insert("pczarn", a("Piotr Czarnecki", "pioczarn@gmail.com"));
-        insert("petrhosek", a("Petr Hosek", "phosek@gmail.com"));
-        insert("pickfire", a("Ivan Tham", "pickfire@riseup.net"));
-        insert("poliorcetics", a("Alexis Bourget", "alexis.bourget@gmail.com"));
-        insert("raoulstrackx", a("Raoul Strackx", "raoul.strackx@fortanix.com"));
-        insert("rcvalle", a("Ramon de C Valle", "rcvalle@users.noreply.github.com"));
-        insert("retep998", a("Peter Atashian", "retep998@gmail.com"));
-        insert("richkadel", a("Rich Kadel", "richkadel@google.com"));
-        insert("sanxiyn", a("Seo Sanghyeon", "sanxiyn@gmail.com"));
-        insert("seanmonstar", a("Sean McArthur", "sean@seanmonstar.com"));
-        insert("stjepang", a("Stjepan Glavina", "stjepang@gmail.com"));
-        insert("susurrus", a("Bryant Mairs", "bryant@mai.rs"));
-        insert("swatinem", a("Arpad Borsos", "swatinem@swatinem.de"));
-        insert("thestinger", a("Daniel Micay", "danielmicay@gmail.com"));
-        insert("tlively", a("Thomas Lively", "tlively@google.com"));
-        insert("tromey", a("Tom Tromey", "tom@tromey.com"));
-        insert("vadimcn", a("Vadim Chugunov", "vadimcn@gmail.com"));
-        insert("willcrichton", a("Will Crichton", "wcrichto@cs.stanford.edu"));
-        insert("xfix", a("Konrad Borowski", "konrad@borowski.pw"));
-        insert("yichoi", a("Young-il Choi", "duddlf.choi@samsung.com"));
-        insert("y-nak", a("Yoshitomo Nakanishi", "yurayura.rounin.3@gmail.com"));
-        insert("yurydelendik", a("Yury Delendik", "ydelendik@mozilla.com"));
-        insert("z0w0", a("Zack Corr", "zack@z0w0.me"));
-        insert("zackmdavis", a("Zack M. Davis", "code@zackmdavis.net"));
-
-        // Aliases of r= reviewers to their current GitHub names.
-        // Includes misspellings and changed names.
-        insert("achrichto", alias("alexcrichton"));
-        insert("acrichto", alias("alexcrichton"));
-        insert("alexchrichton", alias("alexcrichton"));
-        insert("alexcirchton", alias("alexcrichton"));
-        insert("alexcrhiton", alias("alexcrichton"));
-        insert("alexcrichto", alias("alexcrichton"));
-        insert("alexcricthon", alias("alexcrichton"));
-        insert("alexcricton", alias("alexcrichton"));
-        insert("alexcritchton", alias("alexcrichton"));
-        insert("amaneiu", alias("amanieu"));
-        insert("arielb", alias("arielb1"));
-        insert("bson", alias("brson"));
-        insert("cgillot", alias("cjgillot"));
-        insert("compiler=errors", alias("compiler-errors"));
-        insert("cramert", alias("cramertj"));
-        insert("cjgillo", alias("cjgillot"));
-        insert("cupiver", alias("cuviper"));
-        insert("ecstaticmorse", alias("ecstatic-morse"));
-        insert("ekuber", alias("estebank"));
-        insert("frewsxcvx", alias("frewsxcv"));
-        insert("frewsxcxv", alias("frewsxcv"));
-        insert("gankro", alias("gankra"));
-        insert("guilliamegomez", alias("guillaumegomez"));
-        insert("guilliaumegomez", alias("guillaumegomez"));
-        insert("guillaumegomezp", alias("guillaumegomez"));
-        insert("hi-rustin", alias("0xpoe"));
-        insert("huon", alias("huonw"));
-        insert("imperio", alias("guillaumegomez"));
-        insert("icnr", alias("lcnr"));
-        insert("jackh276", alias("jackh726"));
-        insert("jakub-", alias("jakub"));
-        insert("jonathandturner", alias("sophiajt"));
-        insert("jubilee", alias("workingjubilee"));
-        insert("jyn541", alias("jyn514"));
-        insert("jyn", alias("jyn514"));
-        insert("llogic", alias("llogiq"));
-        insert("lncr", alias("lcnr"));
-        insert("lolbinary", alias("lolbinarycat"));
-        insert("manisheart", alias("manishearth"));
-        insert("mark-simulacru", alias("mark-simulacrum"));
-        insert("mark-simulcrum", alias("mark-simulacrum"));
-        insert("marksimulacrum", alias("mark-simulacrum"));
-        insert("mw", alias("michaelwoerister"));
-        insert("ncr", alias("nrc"));
-        insert("nick29581", alias("nrc"));
-        insert("nilstrieb", alias("noratrieb"));
-        insert("nmatsakis", alias("nikomatsakis"));
-        insert("obi-obk", alias("oli-obk"));
-        insert("oli", alias("oli-obk"));
-        insert("oli-bok", alias("oli-obk"));
-        insert("oli-obj", alias("oli-obk"));
-        insert("ozkanonur", alias("onur-ozkan"));
-        insert("petrochencov", alias("petrochenkov"));
-        insert("pietroalbini", alias("emilyalbini"));
-        insert("pwalton", alias("pcwalton"));
-        insert("quietmisdreqvus", alias("quietmisdreavus"));
-        insert("rkruppe", alias("hanna-kruppe"));
-        insert("rustin170506", alias("0xpoe"));
-        insert("simulacrum", alias("mark-simulacrum"));
-        insert("steveklanik", alias("steveklabnik"));
-        insert("steveklbanik", alias("steveklabnik"));
-        insert("wesleyweiser", alias("wesleywiser"));
-        insert("withouboats", alias("withoutboats"));
-        insert("yaahallo", alias("yaahc"));
-
-        Ok(Reviewers { reviewers: map })
+    /// A hash of the reviewer table, which changes whenever a reviewer is
+    /// added, removed or mapped to someone else.
+    pub fn fingerprint(&self) -> u64 {
+        let mut reviewers = self.reviewers.iter().collect::<Vec<_>>();
+        reviewers.sort_by_key(|(name, _)| *name);
+        let mut hasher = StableHasher::new();
+        for (name, author) in reviewers {
+            hasher.write_str(name);
+            hasher.write_str(&author.name);
+            hasher.write_str(&author.email);
+        }
+        let mut ignore = self.ignore.iter().collect::<Vec<_>>();
+        ignore.sort();
+        for token in ignore {
+            hasher.write_str(token);
+        }
+        hasher.finish()
     }
 
     pub fn to_author(&self, reviewer: &str) -> Result<Option<Author>, UnknownReviewer> {
-        let skip = &[
-            "3",
-            "burningtree",
-            "docs",
-            "just",
-            "me",
-            "new",
-            "nobody",
-            "rollup",
-            "rustdoc",
-            "rustdoc-team",
-            "t-rustdoc",
-            "t-rustdoc-frontend",
-            "the-whole-team",
-            "tinyfix",
-            "update",
-        ];
         let reviewer = reviewer.to_lowercase();
-        if skip.contains(&reviewer.as_str()) {
+        if self.ignore.contains(&reviewer) {
             return Ok(None);
         }
         if let Some(v) = self.reviewers.get(reviewer.as_str()).cloned() {
@@ -233,6 +203,71 @@ impl Reviewers {
         }
         Err(UnknownReviewer)
     }
+
+    /// Known usernames close enough to `reviewer` to be likely typos of it,
+    /// best matches first.
+    ///
+    /// Usernames within an edit distance of a quarter of the name's length (but
+    /// at least one) are considered, and at most [`MAX_SUGGESTIONS`] are returned.
+    pub fn suggest(&self, reviewer: &str) -> Vec<Suggestion<'_>> {
+        let reviewer = reviewer.to_lowercase();
+        let max_distance = (reviewer.chars().count() / 4).max(1);
+        let mut suggestions = self
+            .reviewers
+            .iter()
+            .filter_map(|(username, author)| {
+                let distance = edit_distance(&reviewer, username);
+                (distance <= max_distance).then_some(Suggestion {
+                    username,
+                    author,
+                    distance,
+                })
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort_by(|a, b| (a.distance, a.username).cmp(&(b.distance, b.username)));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// The most suggestions returned by [`Reviewers::suggest`].
+const MAX_SUGGESTIONS: usize = 3;
+
+/// A known username which an unknown reviewer might be a typo of.
+pub struct Suggestion<'a> {
+    pub username: &'a str,
+    pub author: &'a Author,
+    /// The number of single character insertions, deletions, substitutions and
+    /// swaps of adjacent characters turning one name into the other.
+    pub distance: usize,
+}
+
+/// The optimal string alignment distance between two strings.
+///
+/// This is the Levenshtein distance, but also counting the swap of two adjacent
+/// characters as a single edit, which is a common kind of typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Three rows of the distance matrix: two back, the previous one and the current one.
+    let mut before = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -246,21 +281,222 @@ impl fmt::Display for UnknownReviewer {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TeamPeople {
-    pub people: HashMap<String, TeamPerson>,
+    pub people: BTreeMap<String, TeamPerson>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct TeamPerson {
     pub name: String,
     pub email: Option<String>,
 }
 
-pub fn get_team_people() -> Result<TeamPeople, Box<dyn Error>> {
-    Ok(
-        ureq::get("https://team-api.infra.rust-lang.org/v1/people.json")
-            .call()?
-            .into_json::<TeamPeople>()?,
-    )
+pub fn get_team_people(url: &str) -> Result<TeamPeople, Box<dyn Error>> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(TIMEOUT)
+        .timeout(TIMEOUT)
+        .build();
+    Ok(agent.get(url).call()?.into_json::<TeamPeople>()?)
+}
+
+/// Read a copy of the team people data previously saved to disk.
+pub fn read_team_people(path: &Path) -> Result<TeamPeople, Box<dyn Error>> {
+    let read = || -> Result<TeamPeople, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    };
+    read()
+        .map_err(|e| ErrorContext(format!("reading team people from {}", path.display()), e).into())
+}
+
+/// Save a copy of the team people data, so later runs can fall back to it.
+///
+/// The file is replaced atomically, so a failed write never clobbers the last
+/// good snapshot.
+pub fn write_team_people(path: &Path, people: &TeamPeople) -> Result<(), Box<dyn Error>> {
+    let write = || -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(people)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    };
+    write()
+        .map_err(|e| ErrorContext(format!("writing team people to {}", path.display()), e).into())
+}
+
+/// Load the team people data according to the settings, if any is configured.
+///
+/// Freshly fetched data is saved to the snapshot, which is read instead when
+/// no URL is configured, in offline mode, or when the request fails.
+fn load_team_people(settings: &ReviewerSettings) -> Result<Option<TeamPeople>, Box<dyn Error>> {
+    let snapshot = settings.team_people_snapshot.as_deref();
+    let url = match (&settings.team_people, snapshot) {
+        (None, None) => return Ok(None),
+        (None, Some(path)) => return read_team_people(path).map(Some),
+        (Some(url), _) => url,
+    };
+    if crate::is_offline() {
+        return match snapshot {
+            Some(path) => read_team_people(path).map(Some),
+            None => Err(ErrorMessage(format!(
+                "team people are fetched from {url}, but no `team-people-snapshot` \
+                 is configured to read them from in offline mode"
+            ))
+            .into()),
+        };
+    }
+    match (get_team_people(url), snapshot) {
+        (Ok(people), Some(path)) => {
+            if let Err(e) = write_team_people(path, &people) {
+                eprintln!("WARNING: failed to save team people snapshot: {e}");
+            }
+            Ok(Some(people))
+        }
+        (Ok(people), None) => Ok(Some(people)),
+        (Err(e), Some(path)) if path.exists() => {
+            eprintln!(
+                "WARNING: failed to fetch team people from {url} ({e}), using {}",
+                path.display()
+            );
+            read_team_people(path).map(Some)
+        }
+        (Err(e), _) => Err(ErrorContext(format!("fetching team people from {url}"), e).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn person(name: &str, email: Option<&str>) -> TeamPerson {
+        TeamPerson {
+            name: name.into(),
+            email: email.map(String::from),
+        }
+    }
+
+    const DATA: &str = r#"
+        version = 1
+        ignore = ["rollup"]
+
+        [reviewers]
+        someone = { name = "Some One", email = "someone@example.com" }
+
+        [aliases]
+        simulacrum = "Mark-Simulacrum"
+        some1 = "someone"
+    "#;
+
+    #[test]
+    fn default_data_parses() {
+        ReviewerData::from_str(DEFAULT_DATA).unwrap();
+    }
+
+    #[test]
+    fn invalid_data() {
+        let invalid = [
+            "version = 2",
+            "version = 1\nignore = [\"me\", \"Me\"]",
+            "version = 1\n[reviewers]\nme = { name = \"Me\", email = \"me@example.com\" }\n\
+             [aliases]\nME = \"other\"",
+            "version = 1\n[aliases]\na = \"b\"\nb = \"c\"",
+            "version = 1\nignore = [\"b\"]\n[aliases]\na = \"b\"",
+        ];
+        for data in invalid {
+            assert!(ReviewerData::from_str(data).is_err(), "{data}");
+        }
+    }
+
+    #[test]
+    fn reviewers_from_team_people() {
+        let mut people = BTreeMap::new();
+        people.insert(
+            "Mark-Simulacrum".into(),
+            person("Mark Rousskov", Some("mark@example.com")),
+        );
+        people.insert("no-email".into(), person("Hidden", None));
+        let data = ReviewerData::from_str(DATA).unwrap();
+        let reviewers = Reviewers::from_data(Some(TeamPeople { people }), data, false).unwrap();
+
+        let mark = Author::new("Mark Rousskov".into(), "mark@example.com".into());
+        let found = reviewers.to_author("mark-simulacrum").unwrap();
+        assert_eq!(found, Some(mark.clone()));
+        // Aliases resolve to the person they point at.
+        assert_eq!(reviewers.to_author("Simulacrum").unwrap(), Some(mark));
+        let someone = Author::new("Some One".into(), "someone@example.com".into());
+        assert_eq!(reviewers.to_author("some1").unwrap(), Some(someone));
+        assert_eq!(reviewers.to_author("rollup").unwrap(), None);
+        assert!(reviewers.to_author("no-email").is_err());
+        assert!(reviewers.to_author("nobody-at-all").is_err());
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("alexcrichton", "alexcrichto"), 1);
+        assert_eq!(edit_distance("steveklabnik", "steveklbanik"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggestions() {
+        let data = ReviewerData::from_str(DATA).unwrap();
+        let reviewers = Reviewers::from_data(None, data, false).unwrap();
+        let suggestions = reviewers.suggest("SomOne");
+        let usernames = suggestions.iter().map(|s| s.username).collect::<Vec<_>>();
+        assert_eq!(usernames, ["someone"]);
+        assert_eq!(suggestions[0].distance, 1);
+        assert!(reviewers.suggest("unrelated").is_empty());
+    }
+
+    #[test]
+    fn alias_to_unknown_reviewer() {
+        let people = TeamPeople {
+            people: BTreeMap::new(),
+        };
+        let data = ReviewerData::from_str(DATA).unwrap();
+        let reviewers = Reviewers::from_data(Some(people), data, false).unwrap();
+        assert!(reviewers.to_author("simulacrum").is_err());
+        assert!(reviewers.to_author("some1").unwrap().is_some());
+
+        let people = TeamPeople {
+            people: BTreeMap::new(),
+        };
+        let data = ReviewerData::from_str(DATA).unwrap();
+        assert!(Reviewers::from_data(Some(people), data, true).is_err());
+    }
+
+    #[test]
+    fn conflicts_with_team_people() {
+        let people = || {
+            let mut people = BTreeMap::new();
+            people.insert(
+                "Mark-Simulacrum".into(),
+                person("Mark Rousskov", Some("mark@example.com")),
+            );
+            people.insert(
+                "someone".into(),
+                person("Some One", Some("someone@example.com")),
+            );
+            TeamPeople { people }
+        };
+        let data = || ReviewerData::from_str(DATA).unwrap();
+        assert!(Reviewers::from_data(Some(people()), data(), false).is_ok());
+        assert!(Reviewers::from_data(Some(people()), data(), true).is_err());
+        // Without the conflicting entry, strict mode accepts the same data.
+        let data = ReviewerData::from_str(
+            "version = 1\n[aliases]\nsimulacrum = \"Mark-Simulacrum\"\nsome1 = \"someone\"",
+        )
+        .unwrap();
+        assert!(Reviewers::from_data(Some(people()), data, true).is_ok());
+
+        // An alias named like a team member shadows them.
+        let data = ReviewerData::from_str("version = 1\n[aliases]\nsomeone = \"Mark-Simulacrum\"")
+            .unwrap();
+        assert!(Reviewers::from_data(Some(people()), data, true).is_err());
+    }
 }
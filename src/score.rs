@@ -1,4 +1,5 @@
 use crate::AuthorMap;
+use crate::hash::StableHasher;
 use std::collections::HashMap;
 use unicase::UniCase;
 
@@ -8,15 +9,34 @@ pub struct AuthorScore {
     pub author: String,
     pub email: String,
     pub commits: usize,
+    pub authored: usize,
+    pub reviewed: usize,
+    pub co_authored: usize,
+    pub tested: usize,
+    pub reported: usize,
+    pub suggested: usize,
+    /// Identifies the person's profile page, see [`person_slug`].
+    pub slug: String,
+}
+
+/// Derive the identifier of a person's profile page from their canonical email.
+///
+/// This is the 64-bit FNV-1a hash of the lowercased email, so it doesn't change
+/// between runs or Rust versions and doesn't reveal the email itself.
+pub fn person_slug(email: &str) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.write(email.to_lowercase().as_bytes());
+    format!("{:016x}", hasher.finish())
 }
 
 pub fn author_map_to_scores(map: &AuthorMap) -> Vec<AuthorScore> {
     let debug_emails = std::env::var("DEBUG_EMAILS").is_ok_and(|value| value == "1");
 
     let scores = map
-        .iter()
-        .map(|(author, commits)| {
+        .contributions()
+        .map(|(author, contributions)| {
             let name = UniCase::into_inner(author.name.clone());
+            let email = UniCase::into_inner(author.email.clone());
 
             AuthorScore {
                 rank: 0,
@@ -25,8 +45,15 @@ pub fn author_map_to_scores(map: &AuthorMap) -> Vec<AuthorScore> {
                 } else {
                     name
                 },
-                email: UniCase::into_inner(author.email.clone()),
-                commits,
+                slug: person_slug(&email),
+                email,
+                commits: contributions.commits,
+                authored: contributions.authored,
+                reviewed: contributions.reviewed,
+                co_authored: contributions.co_authored,
+                tested: contributions.tested,
+                reported: contributions.reported,
+                suggested: contributions.suggested,
             }
         })
         .collect::<Vec<_>>();
@@ -60,12 +87,12 @@ pub fn author_map_to_scores(map: &AuthorMap) -> Vec<AuthorScore> {
 /// Deduplicate scores based on the assumption that an e-mail uniquely identifies a given
 /// person. If there are multiple entries with the same email, their commit counts will be
 /// merged into a single entry, with the canonical name being chosen based on the entry with
-/// the most commits.
+/// the most commits. Emails are compared ignoring case, like in [`person_slug`].
 fn deduplicate_scores(entries: Vec<AuthorScore>) -> Vec<AuthorScore> {
     let mut entry_map: HashMap<String, Vec<AuthorScore>> = HashMap::with_capacity(entries.len());
     for entry in entries {
         entry_map
-            .entry(entry.email.clone())
+            .entry(entry.email.to_lowercase())
             .or_default()
             .push(entry);
     }
@@ -82,7 +109,40 @@ fn deduplicate_scores(entries: Vec<AuthorScore>) -> Vec<AuthorScore> {
                 author: canonical_entry.author.clone(),
                 email: canonical_entry.email.clone(),
                 commits: entry.iter().map(|e| e.commits).sum(),
+                authored: entry.iter().map(|e| e.authored).sum(),
+                reviewed: entry.iter().map(|e| e.reviewed).sum(),
+                co_authored: entry.iter().map(|e| e.co_authored).sum(),
+                tested: entry.iter().map(|e| e.tested).sum(),
+                reported: entry.iter().map(|e| e.reported).sum(),
+                suggested: entry.iter().map(|e| e.suggested).sum(),
+                slug: canonical_entry.slug.clone(),
             }
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ContributionKind;
+    use git2::Oid;
+    use mailmap::Author;
+
+    #[test]
+    fn emails_differing_in_case_are_one_person() {
+        let oid = |n: u8| Oid::from_bytes(&[n; 20]).unwrap();
+        let mut map = AuthorMap::new();
+        let jane = Author::new("Jane".into(), "Jane@Example.com".into());
+        map.add(jane.clone(), oid(1), ContributionKind::Authored);
+        map.add(jane, oid(2), ContributionKind::Authored);
+        let renamed = Author::new("Jane Doe".into(), "jane@example.com".into());
+        map.add(renamed, oid(3), ContributionKind::Reviewed);
+
+        let scores = author_map_to_scores(&map);
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].author, "Jane");
+        assert_eq!(scores[0].commits, 3);
+        assert_eq!(scores[0].reviewed, 1);
+        assert_eq!(scores[0].slug, person_slug("jane@example.com"));
+    }
+}
@@ -0,0 +1,523 @@
+use crate::ContributionKind;
+use crate::csv::Column;
+use crate::error::{ErrorContext, ErrorMessage};
+use mailmap::Author;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The configuration used when there is no `thanks.toml` in the working directory.
+const DEFAULT_SETTINGS: &str = include_str!("../thanks.toml");
+
+/// The path the configuration is read from, unless overridden by `THANKS_CONFIG`.
+const DEFAULT_PATH: &str = "thanks.toml";
+
+/// Top-level paths of the site which project slugs must not collide with.
+const RESERVED_SLUGS: &[&str] = &[
+    "about", "all-time", "csv", "fonts", "images", "json", "people", "styles",
+];
+
+/// Everything thanks needs to know about what to walk and where to render it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub site: SiteSettings,
+    /// The projects whose releases are tracked, in the order they are listed on the site.
+    pub projects: Vec<ProjectSettings>,
+    #[serde(default)]
+    pub reviewers: ReviewerSettings,
+    #[serde(default)]
+    pub csv: CsvSettings,
+    #[serde(default)]
+    pub reports: ReportSettings,
+    #[serde(default)]
+    pub privacy: PrivacySettings,
+    #[serde(default)]
+    pub bots: BotSettings,
+    #[serde(default)]
+    pub trailers: TrailerSettings,
+    /// Where the contributors of finished releases are cached, if anywhere.
+    pub cache: Option<CacheSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SiteSettings {
+    /// Name used in page titles and next to the logo.
+    pub name: String,
+    /// Directory all output is written to.
+    pub output: PathBuf,
+    /// Links shown in the navigation bar.
+    #[serde(default)]
+    pub nav: Vec<NavLink>,
+    /// Image shown in the navigation bar, as a path on the site or a URL.
+    pub logo: Option<String>,
+    /// HTML introducing the site on the about page.
+    pub about: Option<String>,
+    /// Where bugs and requests about the site are filed.
+    pub issues_url: Option<String>,
+    /// Email address for requests which shouldn't be discussed publicly.
+    pub contact: Option<String>,
+}
+
+#[derive(Debug, Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NavLink {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProjectSettings {
+    /// Prefix for release names, e.g. "Rust" for "Rust 1.0.0".
+    pub name: String,
+    /// Path segment release pages are rendered under.
+    pub slug: String,
+    /// URL of the repository whose tags and history are walked.
+    pub repository: String,
+    #[serde(default)]
+    pub tags: TagSettings,
+    #[serde(default)]
+    pub submodules: SubmoduleSettings,
+    /// A local mailmap applied after the ones of the repository and its
+    /// submodules, overriding them.
+    pub mailmap_overrides: Option<PathBuf>,
+    /// The revision of the repository whose mailmaps are applied.
+    #[serde(default)]
+    pub mailmap_revision: MailmapRevision,
+    /// How the reviewers of commits are recorded, tried in order.
+    #[serde(default = "default_extractors")]
+    pub reviewer_extractors: Vec<ExtractorKind>,
+}
+
+/// A way of recording reviewers in commits, see [`crate::extractors`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExtractorKind {
+    /// Merges by the original bors (homu), with `r=` reviewers.
+    Bors,
+    /// Merges by the rewritten bors, `rust-bors[bot]`.
+    RustBors,
+    /// Pull requests merged by GitHub, including through its merge queue.
+    MergeQueue,
+    /// `Reviewed-by:` and `Acked-by:` trailers on any commit.
+    Trailers,
+}
+
+fn default_extractors() -> Vec<ExtractorKind> {
+    vec![ExtractorKind::Bors, ExtractorKind::RustBors]
+}
+
+/// Which revision of a project's mailmaps its contributors are canonicalized
+/// with.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum MailmapRevision {
+    /// The latest commit, written as `HEAD`.
+    #[default]
+    Head,
+    /// The commit of each release, for that release, written as `release`.
+    Release,
+    /// The given commit, tag or branch.
+    Commit(String),
+}
+
+impl From<String> for MailmapRevision {
+    fn from(revision: String) -> Self {
+        match revision.as_str() {
+            "HEAD" => MailmapRevision::Head,
+            "release" => MailmapRevision::Release,
+            _ => MailmapRevision::Commit(revision),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TagSettings {
+    /// Prefix stripped from tag names before parsing them as versions, e.g. `v`.
+    /// Tags without this prefix are ignored.
+    #[serde(default)]
+    pub prefix: String,
+    /// Branch tracking the upcoming release, rendered as "Beta".
+    pub beta_branch: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SubmoduleSettings {
+    /// Submodules are only walked if their URL contains one of these strings.
+    pub include: Vec<String>,
+    /// Repositories which are never walked, even if they match `include`.
+    pub exclude: Vec<String>,
+}
+
+impl SubmoduleSettings {
+    /// Whether the submodule with the given URL should be walked.
+    pub fn matches(&self, url: &str) -> bool {
+        let url = url.to_lowercase();
+        let url = url.strip_suffix(".git").unwrap_or(&url);
+        let included = self.include.iter().any(|i| url.contains(i.as_str()));
+        let excluded = self.exclude.iter().any(|e| {
+            let e = e.to_lowercase();
+            e.strip_suffix(".git").unwrap_or(&e) == url
+        });
+        included && !excluded
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BotSettings {
+    /// What to do with commits credited to bots.
+    #[serde(default)]
+    pub action: BotAction,
+    /// Authors whose name contains one of these, ignoring case, are bots.
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Emails of bots, compared ignoring case.
+    #[serde(default)]
+    pub emails: Vec<String>,
+    /// Domains only bots have emails at, compared ignoring case.
+    #[serde(default)]
+    pub domains: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BotAction {
+    /// Leave their commits out entirely.
+    #[default]
+    Drop,
+    /// Leave them out of the rankings, but list them in a report.
+    Report,
+}
+
+impl BotSettings {
+    /// Whether the given author is a bot.
+    pub fn matches(&self, author: &Author) -> bool {
+        let name = author.name.to_lowercase();
+        let email = author.email.as_str();
+        let domain = email.rsplit_once('@').map(|(_, domain)| domain);
+        self.names
+            .iter()
+            .any(|n| name.contains(n.to_lowercase().as_str()))
+            || self.emails.iter().any(|e| e.eq_ignore_ascii_case(email))
+            || domain
+                .is_some_and(|domain| self.domains.iter().any(|d| d.eq_ignore_ascii_case(domain)))
+    }
+}
+
+/// How people named in commit trailers are credited, by the key of the
+/// trailer, compared ignoring case.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct TrailerSettings(BTreeMap<String, ContributionKind>);
+
+impl Default for TrailerSettings {
+    fn default() -> Self {
+        TrailerSettings(BTreeMap::from([
+            ("Co-authored-by".into(), ContributionKind::CoAuthored),
+            ("Reviewed-by".into(), ContributionKind::Reviewed),
+            ("Tested-by".into(), ContributionKind::Tested),
+            ("Reported-by".into(), ContributionKind::Reported),
+            ("Suggested-by".into(), ContributionKind::Suggested),
+        ]))
+    }
+}
+
+impl TrailerSettings {
+    /// How someone named in a trailer with the given key is credited, if at all.
+    pub fn kind(&self, key: &str) -> Option<ContributionKind> {
+        self.0
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, kind)| *kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ContributionKind)> {
+        self.0.iter().map(|(key, kind)| (key.as_str(), *kind))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ReportSettings {
+    /// Directory the reports for maintainers are written to. It should be
+    /// outside the site's output, so that the reports aren't published.
+    pub directory: PathBuf,
+}
+
+impl Default for ReportSettings {
+    fn default() -> Self {
+        ReportSettings {
+            directory: PathBuf::from("reports"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CacheSettings {
+    /// Directory holding the cache entries. Anything else in it is left alone.
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CsvSettings {
+    /// The columns written to CSV files, in order.
+    pub columns: Vec<Column>,
+}
+
+impl Default for CsvSettings {
+    fn default() -> Self {
+        CsvSettings {
+            columns: vec![
+                Column::Rank,
+                Column::Author,
+                Column::Email,
+                Column::Commits,
+                Column::Authored,
+                Column::Reviewed,
+                Column::CoAuthored,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ReviewerSettings {
+    /// URL of the rust-lang/team style `people.json` mapping usernames to people.
+    pub team_people: Option<String>,
+    /// A local copy of `team_people`, used instead of it in offline mode.
+    pub team_people_snapshot: Option<PathBuf>,
+    /// Reviewers, aliases and ignored tokens missing from the team data. The
+    /// copy built into the binary is used if this isn't set.
+    pub data: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PrivacySettings {
+    /// People who asked not to be listed by name.
+    #[serde(default)]
+    pub exclude: Vec<Exclusion>,
+}
+
+/// A person to leave off the site, identified by exactly one of their
+/// canonical email or GitHub username.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Exclusion {
+    pub email: Option<String>,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub action: ExclusionAction,
+    /// Identifies an anonymized person's profile, which is required for them
+    /// and must never change, or their profile would move.
+    pub id: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExclusionAction {
+    /// Leave the person out entirely.
+    #[default]
+    Drop,
+    /// Keep their contributions, but show them as an anonymous contributor.
+    Anonymize,
+}
+
+impl Settings {
+    /// Load the settings from `THANKS_CONFIG`, or `thanks.toml` in the working
+    /// directory, falling back to the built-in defaults if neither exists.
+    pub fn load() -> Result<Settings, Box<dyn std::error::Error>> {
+        match std::env::var_os("THANKS_CONFIG") {
+            Some(path) => Settings::from_file(Path::new(&path)),
+            None if Path::new(DEFAULT_PATH).exists() => {
+                Settings::from_file(Path::new(DEFAULT_PATH))
+            }
+            None => Settings::from_str(DEFAULT_SETTINGS),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Settings, Box<dyn std::error::Error>> {
+        let parse = || -> Result<Settings, Box<dyn std::error::Error>> {
+            Settings::from_str(&std::fs::read_to_string(path)?)
+        };
+        parse().map_err(|e| {
+            ErrorContext(format!("loading settings from {}", path.display()), e).into()
+        })
+    }
+
+    fn from_str(s: &str) -> Result<Settings, Box<dyn std::error::Error>> {
+        let settings: Settings = toml::from_str(s)?;
+        if settings.projects.is_empty() {
+            return Err(ErrorMessage("at least one project must be configured".into()).into());
+        }
+        let mut slugs = HashSet::new();
+        for project in &settings.projects {
+            if RESERVED_SLUGS.contains(&project.slug.as_str()) {
+                return Err(
+                    ErrorMessage(format!("project slug {:?} is reserved", project.slug)).into(),
+                );
+            }
+            if !slugs.insert(&project.slug) {
+                return Err(
+                    ErrorMessage(format!("duplicate project slug {:?}", project.slug)).into(),
+                );
+            }
+        }
+        let mut keys = HashSet::new();
+        for (key, _) in settings.trailers.iter() {
+            if !keys.insert(key.to_lowercase()) {
+                return Err(ErrorMessage(format!(
+                    "trailer {:?} is listed more than once (keys are compared ignoring case)",
+                    key
+                ))
+                .into());
+            }
+        }
+        let mut ids = HashSet::new();
+        for exclusion in &settings.privacy.exclude {
+            if exclusion.email.is_some() == exclusion.username.is_some() {
+                return Err(ErrorMessage(format!(
+                    "privacy exclusions need exactly one of `email` or `username`, found {:?}",
+                    exclusion
+                ))
+                .into());
+            }
+            match &exclusion.id {
+                None if exclusion.action == ExclusionAction::Anonymize => {
+                    return Err(ErrorMessage(format!(
+                        "anonymized privacy exclusions need an `id`, found {:?}",
+                        exclusion
+                    ))
+                    .into());
+                }
+                None => {}
+                Some(id) => {
+                    let valid = !id.is_empty()
+                        && id
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                    if !valid {
+                        return Err(ErrorMessage(format!(
+                            "privacy exclusion ids may only contain letters, digits, `-` and `_`, found {:?}",
+                            id
+                        ))
+                        .into());
+                    }
+                    if !ids.insert(id.to_lowercase()) {
+                        return Err(ErrorMessage(format!(
+                            "duplicate privacy exclusion id {:?}",
+                            id
+                        ))
+                        .into());
+                    }
+                }
+            }
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_settings_parse() {
+        let settings = Settings::from_str(DEFAULT_SETTINGS).unwrap();
+        let submodules = &settings.projects[0].submodules;
+        assert!(submodules.matches("https://github.com/rust-lang/cargo.git"));
+        assert!(submodules.matches("https://github.com/rust-lang/cargo"));
+        assert!(!submodules.matches("https://github.com/rust-lang/llvm-project"));
+        assert!(!submodules.matches("https://github.com/RUST-LANG/gcc.git"));
+        assert!(!submodules.matches("https://github.com/someone/else.git"));
+        assert_eq!(settings.projects[0].mailmap_revision, MailmapRevision::Head);
+        assert_eq!(
+            settings.projects[0].reviewer_extractors,
+            default_extractors()
+        );
+        assert_eq!(
+            settings.trailers.kind("tested-by"),
+            Some(ContributionKind::Tested)
+        );
+        assert_eq!(settings.trailers.kind("Signed-off-by"), None);
+    }
+
+    #[test]
+    fn default_bots() {
+        let bots = Settings::from_str(DEFAULT_SETTINGS).unwrap().bots;
+        let author = |name: &str, email: &str| Author::new(name.into(), email.into());
+        for bot in [
+            author("bors", "Bors@Rust-Lang.org"),
+            author("rust-bors[bot]", "rust-bors@example.com"),
+            author(
+                "dependabot[bot]",
+                "49699333+dependabot[bot]@users.noreply.github.com",
+            ),
+            author(
+                "github-actions[bot]",
+                "41898282+github-actions[bot]@users.noreply.github.com",
+            ),
+            author("rustbot", "rustbot@example.com"),
+            author("The Rustbot", "rustbot@example.com"),
+        ] {
+            assert!(bots.matches(&bot), "{:?}", bot);
+        }
+        assert!(!bots.matches(&author(
+            "Jane Doe",
+            "12345+janedoe@users.noreply.github.com"
+        )));
+    }
+
+    #[test]
+    fn reserved_slugs() {
+        let with_slug = |slug: &str| {
+            let toml =
+                DEFAULT_SETTINGS.replacen("slug = \"rust\"", &format!("slug = {:?}", slug), 1);
+            Settings::from_str(&toml)
+        };
+        assert!(with_slug("cargo").is_ok());
+        for slug in ["csv", "json", "people", "all-time"] {
+            assert!(with_slug(slug).is_err(), "{}", slug);
+        }
+    }
+
+    #[test]
+    fn mailmap_revisions() {
+        let revision = |s: &str| MailmapRevision::from(s.to_string());
+        assert_eq!(revision("HEAD"), MailmapRevision::Head);
+        assert_eq!(revision("release"), MailmapRevision::Release);
+        assert_eq!(revision("1.50.0"), MailmapRevision::Commit("1.50.0".into()));
+    }
+
+    #[test]
+    fn exclusion_ids() {
+        let with_exclusions = |exclusions: &str| {
+            let (before, after) = DEFAULT_SETTINGS.split_once("[privacy]").unwrap();
+            let after = after.replacen("exclude = [", &format!("exclude = [{}", exclusions), 1);
+            Settings::from_str(&format!("{}[privacy]{}", before, after))
+        };
+        assert!(
+            with_exclusions(r#"{ username = "a", action = "anonymize", id = "a-1" },"#).is_ok()
+        );
+        assert!(with_exclusions(r#"{ email = "a@example.com" },"#).is_ok());
+        assert!(with_exclusions(r#"{ username = "a", action = "anonymize" },"#).is_err());
+        assert!(
+            with_exclusions(r#"{ username = "a", action = "anonymize", id = "a 1" },"#).is_err()
+        );
+        assert!(
+            with_exclusions(
+                r#"{ username = "a", action = "anonymize", id = "x" },
+                   { username = "b", action = "anonymize", id = "X" },"#
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,32 +1,46 @@
 use crate::score::AuthorScore;
-use crate::{AuthorMap, AuthorsWithScores, VersionTag};
+use crate::settings::{NavLink, Settings, SiteSettings};
+use crate::{AuthorsWithScores, ProjectThanks};
 use handlebars::Handlebars;
-use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 pub fn render(
-    by_version: BTreeMap<VersionTag, AuthorsWithScores>,
+    settings: &Settings,
+    projects: &[ProjectThanks<'_>],
     all_time_map: AuthorsWithScores,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    copy_public()?;
-    index(&all_time_map.authors, &by_version)?;
-    about()?;
-    releases(&by_version, &all_time_map)?;
+    let site = &settings.site;
+    copy_public(&site.output)?;
+    index(site, projects, &all_time_map)?;
+    about(site, settings)?;
+    all_time(site, &all_time_map)?;
+    for project in projects {
+        project_index(site, project)?;
+        releases(site, project, back_url(projects, project))?;
+    }
+    people(site, projects, &all_time_map)?;
 
     Ok(())
 }
 
 #[derive(serde::Serialize)]
-struct CommonData {
+struct CommonData<'a> {
     title: String,
+    site_name: &'a str,
+    nav: &'a [NavLink],
+    logo: Option<&'a str>,
     show_thanks_in_logo: bool,
 }
 
-impl CommonData {
-    fn new(title: String) -> Self {
+impl<'a> CommonData<'a> {
+    fn new(site: &'a SiteSettings, title: String) -> Self {
         CommonData {
             title,
+            site_name: &site.name,
+            nav: &site.nav,
+            logo: site.logo.as_deref(),
             show_thanks_in_logo: true,
         }
     }
@@ -53,135 +67,372 @@ fn create_dir<P: AsRef<Path>>(p: P) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn copy_public() -> Result<(), Box<dyn std::error::Error>> {
+fn copy_public(output: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let wd = walkdir::WalkDir::new("public");
-    fs::create_dir_all("output")?;
+    fs::create_dir_all(output)?;
     for entry in wd {
         let entry = entry?;
         if entry.file_type().is_file() {
             fs::copy(
                 entry.path(),
-                Path::new("output").join(entry.path().strip_prefix("public/")?),
+                output.join(entry.path().strip_prefix("public/")?),
             )?;
         } else if entry.file_type().is_dir() {
-            create_dir(Path::new("output").join(entry.path().strip_prefix("public/")?))?;
+            create_dir(output.join(entry.path().strip_prefix("public/")?))?;
         }
     }
     Ok(())
 }
 
-fn index(
-    all_time: &AuthorMap,
-    by_version: &BTreeMap<VersionTag, AuthorsWithScores>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    #[derive(serde::Serialize)]
-    struct Release {
-        name: String,
-        url: String,
-        people: usize,
-        commits: usize,
+#[derive(serde::Serialize)]
+struct Release {
+    name: String,
+    url: String,
+    people: usize,
+    commits: usize,
+}
+
+impl Release {
+    fn new(name: String, url: String, authors: &AuthorsWithScores) -> Self {
+        Release {
+            name,
+            url,
+            people: authors.people(),
+            commits: authors.contributions(),
+        }
     }
-    #[derive(serde::Serialize)]
-    struct Index {
-        common: CommonData,
-        releases: Vec<Release>,
+}
+
+#[derive(serde::Serialize)]
+struct Index<'a> {
+    common: CommonData<'a>,
+    heading: String,
+    releases: Vec<Release>,
+}
+
+/// The page the release pages of a project link back to.
+///
+/// With a single project the landing page lists its releases, so there is no
+/// need to send people to the project page.
+fn back_url(projects: &[ProjectThanks<'_>], project: &ProjectThanks<'_>) -> String {
+    if projects.len() == 1 {
+        String::from("/")
+    } else {
+        format!("/{}/", project.project.slug)
     }
-    let hb = hb()?;
+}
 
+/// The all-time and per-release links for a single project.
+fn project_releases(project: &ProjectThanks<'_>) -> Vec<Release> {
+    let slug = &project.project.slug;
     let mut releases = Vec::new();
-    releases.push(Release {
-        name: "All time".into(),
-        url: "/rust/all-time/".into(),
-        people: all_time.iter().count(),
-        commits: all_time.iter().map(|(_, count)| count).sum(),
-    });
-    for (version, stats) in by_version.iter().rev() {
-        releases.push(Release {
-            name: version.name.clone(),
-            url: format!("/rust/{}/", version.version),
-            people: stats.authors.iter().count(),
-            commits: stats.authors.iter().map(|(_, count)| count).sum(),
-        });
+    releases.push(Release::new(
+        "All time".into(),
+        format!("/{}/all-time/", slug),
+        &project.all_time,
+    ));
+    for (version, stats) in project.by_version.iter().rev() {
+        releases.push(Release::new(
+            version.name.clone(),
+            format!("/{}/{}/", slug, version.version),
+            stats,
+        ));
     }
+    releases
+}
+
+/// Render the landing page.
+///
+/// With a single project this lists its releases; otherwise it links to the
+/// combined leaderboard and to each project's own page.
+fn index(
+    site: &SiteSettings,
+    projects: &[ProjectThanks<'_>],
+    all_time: &AuthorsWithScores,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hb = hb()?;
+
+    let releases = if let [project] = projects {
+        project_releases(project)
+    } else {
+        let mut releases = vec![Release::new(
+            "All time".into(),
+            "/all-time/".into(),
+            all_time,
+        )];
+        for project in projects {
+            releases.push(Release::new(
+                project.project.name.clone(),
+                format!("/{}/", project.project.slug),
+                &project.all_time,
+            ));
+        }
+        releases
+    };
 
     let res = hb.render(
         "index",
         &Index {
-            common: CommonData::new("Rust Contributors".into()).without_thanks_in_logo(),
+            common: CommonData::new(site, format!("{} Contributors", site.name))
+                .without_thanks_in_logo(),
+            heading: String::from("Thanks"),
             releases,
         },
     )?;
 
-    fs::write("output/index.html", res)?;
+    fs::write(site.output.join("index.html"), res)?;
+    Ok(())
+}
+
+/// Render the page listing the releases of a single project.
+fn project_index(
+    site: &SiteSettings,
+    project: &ProjectThanks<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hb = hb()?;
+
+    let res = hb.render(
+        "index",
+        &Index {
+            common: CommonData::new(site, format!("{} Contributors", project.project.name)),
+            heading: project.project.name.clone(),
+            releases: project_releases(project),
+        },
+    )?;
+
+    let dir = site.output.join(&project.project.slug);
+    create_dir(&dir)?;
+    fs::write(dir.join("index.html"), res)?;
     Ok(())
 }
 
-fn about() -> Result<(), Box<dyn std::error::Error>> {
+fn about(site: &SiteSettings, settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
     #[derive(serde::Serialize)]
-    struct About {
-        common: CommonData,
+    struct About<'a> {
+        common: CommonData<'a>,
+        about: Option<&'a str>,
+        repositories: Vec<&'a str>,
+        issues_url: Option<&'a str>,
+        contact: Option<&'a str>,
     }
     let hb = hb()?;
 
     let res = hb.render(
         "about",
         &About {
-            common: CommonData::new("About - Rust Contributors".into()),
+            common: CommonData::new(site, format!("About - {} Contributors", site.name)),
+            about: site.about.as_deref(),
+            repositories: settings
+                .projects
+                .iter()
+                .map(|p| p.repository.trim_end_matches(".git"))
+                .collect(),
+            issues_url: site.issues_url.as_deref(),
+            contact: site.contact.as_deref(),
         },
     )?;
 
-    create_dir("output/about")?;
-    fs::write("output/about/index.html", res)?;
+    let dir = site.output.join("about");
+    create_dir(&dir)?;
+    fs::write(dir.join("index.html"), res)?;
     Ok(())
 }
 
-fn releases(
-    by_version: &BTreeMap<VersionTag, AuthorsWithScores>,
+#[derive(serde::Serialize)]
+struct Stats<'a> {
+    common: CommonData<'a>,
+    release_title: String,
+    release: String,
+    back_url: String,
+    count: usize,
+    scores: &'a [AuthorScore],
+    in_progress: bool,
+}
+
+/// Render the leaderboard combining every project.
+fn all_time(
+    site: &SiteSettings,
     all_time: &AuthorsWithScores,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    #[derive(serde::Serialize)]
-    struct Release<'a> {
-        common: CommonData,
-        release_title: String,
-        release: String,
-        count: usize,
-        scores: &'a [AuthorScore],
-        in_progress: bool,
-    }
     let hb = hb()?;
 
     let scores = &all_time.scores;
     let res = hb.render(
         "stats",
-        &Release {
-            common: CommonData::new("All-time Rust Contributors".into()),
+        &Stats {
+            common: CommonData::new(site, format!("All-time {} Contributors", site.name)),
             release_title: String::from("All-time"),
-            release: String::from("all of Rust"),
+            release: format!("all of {}", site.name),
+            back_url: String::from("/"),
             count: scores.len(),
             scores,
             in_progress: true,
         },
     )?;
 
-    create_dir("output/rust/all-time")?;
-    fs::write("output/rust/all-time/index.html", res)?;
+    let dir = site.output.join("all-time");
+    create_dir(&dir)?;
+    fs::write(dir.join("index.html"), res)?;
+    Ok(())
+}
+
+fn releases(
+    site: &SiteSettings,
+    project: &ProjectThanks<'_>,
+    back_url: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hb = hb()?;
+    let name = &project.project.name;
+    let slug = &project.project.slug;
 
-    for (version, map) in by_version {
+    let scores = &project.all_time.scores;
+    let res = hb.render(
+        "stats",
+        &Stats {
+            common: CommonData::new(site, format!("All-time {} Contributors", name)),
+            release_title: String::from("All-time"),
+            release: format!("all of {}", name),
+            back_url: back_url.clone(),
+            count: scores.len(),
+            scores,
+            in_progress: true,
+        },
+    )?;
+
+    let dir = site.output.join(slug).join("all-time");
+    create_dir(&dir)?;
+    fs::write(dir.join("index.html"), res)?;
+
+    for (version, map) in &project.by_version {
         let scores = &map.scores;
         let res = hb.render(
             "stats",
-            &Release {
-                common: CommonData::new(format!("Rust {} Contributors", version)),
+            &Stats {
+                common: CommonData::new(site, format!("{} {} Contributors", name, version)),
                 release_title: version.name.clone(),
                 release: version.to_string(),
+                back_url: back_url.clone(),
                 count: scores.len(),
                 scores,
                 in_progress: version.in_progress,
             },
         )?;
 
-        create_dir(format!("output/rust/{}", version))?;
-        fs::write(format!("output/rust/{}/index.html", version), res)?;
+        let dir = site.output.join(slug).join(version.to_string());
+        create_dir(&dir)?;
+        fs::write(dir.join("index.html"), res)?;
+    }
+    Ok(())
+}
+
+/// Render a profile page for every person, listing the releases they contributed to.
+///
+/// People are identified by their canonical email, the same way the scores are
+/// deduplicated, and their pages live at `/people/<slug>/`.
+fn people(
+    site: &SiteSettings,
+    projects: &[ProjectThanks<'_>],
+    all_time: &AuthorsWithScores,
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(serde::Serialize)]
+    struct PersonRelease {
+        name: String,
+        url: String,
+        rank: u32,
+        commits: usize,
+        authored: usize,
+        reviewed: usize,
+        co_authored: usize,
+        tested: usize,
+        reported: usize,
+        suggested: usize,
+    }
+    #[derive(serde::Serialize)]
+    struct PersonProject {
+        name: String,
+        first: String,
+        latest: String,
+        releases: Vec<PersonRelease>,
+    }
+    #[derive(serde::Serialize)]
+    struct Person<'a> {
+        common: CommonData<'a>,
+        name: String,
+        rank: u32,
+        commits: usize,
+        authored: usize,
+        reviewed: usize,
+        co_authored: usize,
+        tested: usize,
+        reported: usize,
+        suggested: usize,
+        projects: Vec<PersonProject>,
+    }
+    let hb = hb()?;
+
+    let mut people: HashMap<String, Vec<PersonProject>> = HashMap::new();
+    for project in projects {
+        let mut releases: HashMap<String, Vec<PersonRelease>> = HashMap::new();
+        for (version, stats) in &project.by_version {
+            for score in &stats.scores {
+                releases
+                    .entry(score.email.to_lowercase())
+                    .or_default()
+                    .push(PersonRelease {
+                        name: version.name.clone(),
+                        url: format!("/{}/{}/", project.project.slug, version),
+                        rank: score.rank,
+                        commits: score.commits,
+                        authored: score.authored,
+                        reviewed: score.reviewed,
+                        co_authored: score.co_authored,
+                        tested: score.tested,
+                        reported: score.reported,
+                        suggested: score.suggested,
+                    });
+            }
+        }
+        for (email, mut releases) in releases {
+            // Releases were collected oldest first, but are listed newest first.
+            let first = releases.first().unwrap().name.clone();
+            let latest = releases.last().unwrap().name.clone();
+            releases.reverse();
+            people.entry(email).or_default().push(PersonProject {
+                name: project.project.name.clone(),
+                first,
+                latest,
+                releases,
+            });
+        }
+    }
+
+    for score in &all_time.scores {
+        let projects = people
+            .remove(&score.email.to_lowercase())
+            .unwrap_or_default();
+        let res = hb.render(
+            "person",
+            &Person {
+                common: CommonData::new(
+                    site,
+                    format!("{} - {} Contributors", score.author, site.name),
+                ),
+                name: score.author.clone(),
+                rank: score.rank,
+                commits: score.commits,
+                authored: score.authored,
+                reviewed: score.reviewed,
+                co_authored: score.co_authored,
+                tested: score.tested,
+                reported: score.reported,
+                suggested: score.suggested,
+                projects,
+            },
+        )?;
+
+        let dir = site.output.join("people").join(&score.slug);
+        create_dir(&dir)?;
+        fs::write(dir.join("index.html"), res)?;
     }
     Ok(())
 }
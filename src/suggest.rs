@@ -0,0 +1,347 @@
+//! Drafting `.mailmap` entries for people who appear under several identities.
+//!
+//! Identities are clustered when they likely belong to the same person:
+//!
+//! * they have the same email, which `deduplicate_scores` already counts as one
+//!   person, but under whichever name has the most commits,
+//! * their names are the same after normalization (see [`normalize_name`]),
+//!   for names of at least two words,
+//! * one is a GitHub noreply email for a username which resolves, through the
+//!   reviewer data, to the other one's email, or
+//! * their emails have the same local part (the username, for GitHub noreply
+//!   emails), unless it's too short or generic to identify anyone.
+//!
+//! Every cluster is printed as a block of mailmap lines mapping each identity to
+//! the one with the most commits. These are drafts: the identities are the ones
+//! left after applying the existing mailmap, so each line should be checked
+//! before it is added.
+
+use crate::AuthorsWithScores;
+use crate::privacy::is_placeholder;
+use crate::reviewers::Reviewers;
+use mailmap::Author;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Local parts shared by too many unrelated people to say anything.
+const GENERIC_LOCAL_PARTS: &[&str] = &[
+    "admin", "contact", "dev", "email", "git", "github", "hello", "info", "mail", "noreply",
+    "no-reply", "root", "user",
+];
+
+/// Local parts shorter than this aren't considered, as they are rarely unique.
+const MIN_LOCAL_PART: usize = 4;
+
+/// Why two identities were put in the same cluster.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Reason {
+    SameEmail,
+    SameName,
+    GitHubUsername,
+    SameLocalPart,
+}
+
+impl Reason {
+    fn describe(self) -> &'static str {
+        match self {
+            Reason::SameEmail => "same email",
+            Reason::SameName => "same name",
+            Reason::GitHubUsername => "GitHub noreply email of a known username",
+            Reason::SameLocalPart => "same email local part",
+        }
+    }
+}
+
+/// Identities which likely belong to the same person, most commits first.
+#[derive(Debug)]
+struct Cluster<'a> {
+    identities: Vec<(&'a Author, usize)>,
+    reasons: Vec<Reason>,
+}
+
+/// Lowercase a name, drop punctuation and collapse whitespace, so that e.g.
+/// `Jane  Q. Doe` and `jane q doe` compare equal.
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The username of a GitHub noreply email, either `username@...` or the newer
+/// `12345+username@...`.
+fn github_username(email: &str) -> Option<&str> {
+    const DOMAIN: &str = "@users.noreply.github.com";
+    let split = email.len().checked_sub(DOMAIN.len())?;
+    let (local, domain) = (email.get(..split)?, email.get(split..)?);
+    if !domain.eq_ignore_ascii_case(DOMAIN) {
+        return None;
+    }
+    Some(
+        local
+            .split_once('+')
+            .map_or(local, |(_, username)| username),
+    )
+}
+
+/// The part of an email identifying its owner: the username for GitHub noreply
+/// emails, and what's before the `@` otherwise.
+fn local_part(email: &str) -> Option<String> {
+    let local = github_username(email).or_else(|| email.split_once('@').map(|(l, _)| l))?;
+    let local = local.to_lowercase();
+    let generic = GENERIC_LOCAL_PARTS.contains(&local.as_str());
+    (local.chars().count() >= MIN_LOCAL_PART && !generic).then_some(local)
+}
+
+/// A union-find over identity indices, remembering why sets were joined.
+struct Sets {
+    parent: Vec<usize>,
+    reasons: Vec<Vec<Reason>>,
+}
+
+impl Sets {
+    fn new(len: usize) -> Self {
+        Sets {
+            parent: (0..len).collect(),
+            reasons: vec![Vec::new(); len],
+        }
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parent[idx] != idx {
+            self.parent[idx] = self.parent[self.parent[idx]];
+            idx = self.parent[idx];
+        }
+        idx
+    }
+
+    fn union(&mut self, a: usize, b: usize, reason: Reason) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b] = a;
+            let reasons = std::mem::take(&mut self.reasons[b]);
+            self.reasons[a].extend(reasons);
+        }
+        self.reasons[a].push(reason);
+    }
+}
+
+/// Group identities which likely belong to the same person.
+///
+/// `username_email` looks up the email of a GitHub username, if known.
+/// Placeholders of anonymized people are never grouped with anyone.
+fn clusters<'a>(
+    identities: &[(&'a Author, usize)],
+    username_email: impl Fn(&str) -> Option<String>,
+) -> Vec<Cluster<'a>> {
+    let mut sets = Sets::new(identities.len());
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let mut by_local: HashMap<String, usize> = HashMap::new();
+    let mut by_email: HashMap<String, usize> = HashMap::new();
+    for (idx, (author, _)) in identities.iter().enumerate() {
+        if is_placeholder(author) {
+            continue;
+        }
+        match by_email.get(&author.email.to_lowercase()) {
+            Some(&other) => sets.union(other, idx, Reason::SameEmail),
+            None => {
+                by_email.insert(author.email.to_lowercase(), idx);
+            }
+        }
+    }
+
+    for (idx, (author, _)) in identities.iter().enumerate() {
+        if is_placeholder(author) {
+            continue;
+        }
+        let name = normalize_name(&author.name);
+        if name.contains(' ') {
+            if let Some(&other) = by_name.get(&name) {
+                sets.union(other, idx, Reason::SameName);
+            } else {
+                by_name.insert(name, idx);
+            }
+        }
+        if let Some(local) = local_part(&author.email) {
+            if let Some(&other) = by_local.get(&local) {
+                if sets.find(other) != sets.find(idx) {
+                    sets.union(other, idx, Reason::SameLocalPart);
+                }
+            } else {
+                by_local.insert(local, idx);
+            }
+        }
+        let other = github_username(&author.email)
+            .and_then(&username_email)
+            .and_then(|email| by_email.get(&email.to_lowercase()).copied());
+        if let Some(other) = other.filter(|&other| other != idx) {
+            sets.union(other, idx, Reason::GitHubUsername);
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<(&Author, usize)>> = HashMap::new();
+    for (idx, identity) in identities.iter().enumerate() {
+        let root = sets.find(idx);
+        members.entry(root).or_default().push(*identity);
+    }
+    let mut clusters = members
+        .into_iter()
+        .filter(|(_, identities)| identities.len() > 1)
+        .map(|(root, mut identities)| {
+            identities.sort_by(|(a, a_commits), (b, b_commits)| {
+                b_commits.cmp(a_commits).then_with(|| a.cmp(b))
+            });
+            let mut reasons = std::mem::take(&mut sets.reasons[root]);
+            reasons.sort();
+            reasons.dedup();
+            Cluster {
+                identities,
+                reasons,
+            }
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by(|a, b| {
+        let commits = |c: &Cluster<'_>| c.identities.iter().map(|(_, n)| n).sum::<usize>();
+        commits(b)
+            .cmp(&commits(a))
+            .then_with(|| a.identities[0].0.cmp(b.identities[0].0))
+    });
+    clusters
+}
+
+/// The mailmap line mapping `from` to `to`.
+///
+/// Identities differing only in email get the shorter email-only form, which
+/// also catches commits with other spellings of the name.
+fn mailmap_line(to: &Author, from: &Author) -> String {
+    if to.name == from.name {
+        format!("{} <{}> <{}>", to.name, to.email, from.email)
+    } else {
+        format!("{} <{}> {} <{}>", to.name, to.email, from.name, from.email)
+    }
+}
+
+/// Print draft mailmap entries for the identities in `all_time`.
+pub fn suggest_mailmap(
+    all_time: &AuthorsWithScores,
+    reviewers: &Reviewers,
+    mut out: impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let identities = all_time
+        .authors
+        .iter()
+        .filter(|(author, _)| !author.email.is_empty())
+        .collect::<Vec<_>>();
+    let username_email = |username: &str| match reviewers.to_author(username) {
+        Ok(Some(author)) => Some(author.email.into_inner()),
+        _ => None,
+    };
+
+    for cluster in clusters(&identities, username_email) {
+        let reasons = cluster
+            .reasons
+            .iter()
+            .map(|r| r.describe())
+            .collect::<Vec<_>>();
+        writeln!(out, "# {}", reasons.join(", "))?;
+        let (to, _) = cluster.identities[0];
+        for (from, _) in &cluster.identities[1..] {
+            writeln!(out, "{}", mailmap_line(to, from))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn author(name: &str, email: &str) -> Author {
+        Author::new(name.into(), email.into())
+    }
+
+    #[test]
+    fn local_parts() {
+        assert_eq!(local_part("jane@example.com").as_deref(), Some("jane"));
+        assert_eq!(
+            local_part("123+JaneDoe@users.noreply.github.com").as_deref(),
+            Some("janedoe")
+        );
+        assert_eq!(local_part("me@example.com"), None);
+        assert_eq!(local_part("info@example.com"), None);
+    }
+
+    #[test]
+    fn clusters_identities() {
+        let jane_work = author("Jane Doe", "jane.doe@work.example");
+        let jane_home = author("jane  doe.", "jd@home.example");
+        let jane_github = author("jdoe", "42+janedoe@users.noreply.github.com");
+        let john = author("John", "john@example.com");
+        let john_other = author("John Smith", "john@other.example");
+        let solo = author("Jane", "someone@example.com");
+        let solo_renamed = author("Jane R", "someone@example.com");
+        let identities = [
+            (&jane_work, 10),
+            (&jane_home, 3),
+            (&jane_github, 1),
+            (&john, 5),
+            (&john_other, 2),
+            (&solo, 1),
+            (&solo_renamed, 2),
+        ];
+        let username_email =
+            |username: &str| (username == "janedoe").then(|| "jane.doe@work.example".to_string());
+        let clusters = clusters(&identities, username_email);
+
+        assert_eq!(clusters.len(), 3);
+        let jane = clusters[0]
+            .identities
+            .iter()
+            .map(|(a, _)| *a)
+            .collect::<Vec<_>>();
+        assert_eq!(jane, [&jane_work, &jane_home, &jane_github]);
+        assert_eq!(
+            clusters[0].reasons,
+            [Reason::SameName, Reason::GitHubUsername]
+        );
+        let john_cluster = clusters[1]
+            .identities
+            .iter()
+            .map(|(a, _)| *a)
+            .collect::<Vec<_>>();
+        assert_eq!(john_cluster, [&john, &john_other]);
+        assert_eq!(clusters[1].reasons, [Reason::SameLocalPart]);
+        let renamed = clusters[2]
+            .identities
+            .iter()
+            .map(|(a, _)| *a)
+            .collect::<Vec<_>>();
+        assert_eq!(renamed, [&solo_renamed, &solo]);
+        assert_eq!(clusters[2].reasons, [Reason::SameEmail]);
+
+        assert_eq!(
+            mailmap_line(&jane_work, &jane_home),
+            "Jane Doe <jane.doe@work.example> jane  doe. <jd@home.example>"
+        );
+        assert_eq!(
+            mailmap_line(&john, &author("john", "j@example.com")),
+            "John <john@example.com> <j@example.com>"
+        );
+    }
+
+    #[test]
+    fn placeholders_stay_apart() {
+        let first = author("Anonymous contributor", "a1@anonymous.invalid");
+        let second = author("Anonymous contributor", "a2@anonymous.invalid");
+        let named = author("Someone Else", "a1@example.com");
+        let identities = [(&first, 3), (&second, 2), (&named, 1)];
+        assert!(clusters(&identities, |_| None).is_empty());
+    }
+}
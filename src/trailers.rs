@@ -0,0 +1,163 @@
+//! Parsing the trailers of commit messages, following `git interpret-trailers`.
+//!
+//! Trailers are the `Key: value` lines ending a commit message, such as
+//! `Co-authored-by: Jane Doe <jane@example.com>`. Like git, only the last
+//! paragraph is considered, and only if it's made of trailers, so that a colon
+//! in the body of a message isn't mistaken for one.
+
+use mailmap::Author;
+
+/// Keys git itself adds, which mark a paragraph as trailers even when it
+/// has other lines in it.
+const GIT_KEYS: &[&str] = &["Signed-off-by"];
+
+/// Lines `git cherry-pick -x` adds, which count as trailers without having a
+/// key.
+const CHERRY_PICKED: &str = "(cherry picked from commit ";
+
+/// A `Key: value` line in the trailers of a message.
+#[derive(Debug, PartialEq)]
+pub struct Trailer<'m> {
+    pub key: &'m str,
+    /// The value, with any continuation lines joined to it by spaces.
+    pub value: String,
+}
+
+/// The trailers of a commit message.
+///
+/// These are the lines of the last paragraph of the form `Key: value`, where
+/// the key is made of letters, digits and `-`, and lines starting with
+/// whitespace continue the value of the one before. The paragraph only counts
+/// as trailers if all of its lines are, or if at least a quarter of them are
+/// and one has a key git adds or one `known` accepts. The first paragraph is
+/// the subject, never trailers, and lines starting with `#` are comments.
+pub fn parse<'m>(message: &'m str, known: impl Fn(&str) -> bool) -> Vec<Trailer<'m>> {
+    let lines = message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    let is_blank = |line: &&str| line.trim().is_empty();
+    let end = lines
+        .iter()
+        .rposition(|l| !is_blank(l))
+        .map_or(0, |i| i + 1);
+    let Some(start) = lines[..end].iter().rposition(is_blank) else {
+        return Vec::new();
+    };
+    if lines[..start].iter().all(is_blank) {
+        return Vec::new();
+    }
+
+    let mut trailers: Vec<Trailer<'m>> = Vec::new();
+    let (mut trailer_lines, mut other_lines, mut recognized) = (0, 0, false);
+    // Whether the line before was a trailer, which continuation lines extend.
+    let mut in_trailer = false;
+    for line in &lines[start + 1..end] {
+        if line.starts_with([' ', '\t']) {
+            if in_trailer {
+                let trailer = trailers.last_mut().unwrap();
+                trailer.value.push(' ');
+                trailer.value.push_str(line.trim());
+            }
+        } else if line.starts_with(CHERRY_PICKED) {
+            trailer_lines += 1;
+            recognized = true;
+            in_trailer = false;
+        } else if let Some((key, value)) = split(line) {
+            trailer_lines += 1;
+            recognized |= known(key) || GIT_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key));
+            trailers.push(Trailer {
+                key,
+                value: value.to_string(),
+            });
+            in_trailer = true;
+        } else {
+            other_lines += 1;
+            in_trailer = false;
+        }
+    }
+
+    let is_block =
+        trailer_lines > 0 && (other_lines == 0 || (recognized && trailer_lines * 3 >= other_lines));
+    if is_block { trailers } else { Vec::new() }
+}
+
+/// Split a line into the key and value of a trailer, if it is one.
+fn split(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    // Like git, allow whitespace between the key and the colon.
+    let key = key.trim_end();
+    let is_key = !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '-');
+    is_key.then(|| (key, value.trim()))
+}
+
+/// Parse a trailer value of the form `Name <email>`.
+pub fn person(value: &str) -> Option<Author> {
+    let (name, email) = value.strip_suffix('>')?.rsplit_once('<')?;
+    let (name, email) = (name.trim(), email.trim());
+    if name.is_empty() || email.is_empty() {
+        return None;
+    }
+    Some(Author::new(name.into(), email.into()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys<'m>(message: &'m str, known: &[&str]) -> Vec<(&'m str, String)> {
+        parse(message, |key| {
+            known.iter().any(|k| k.eq_ignore_ascii_case(key))
+        })
+        .into_iter()
+        .map(|t| (t.key, t.value))
+        .collect()
+    }
+
+    #[test]
+    fn trailer_block() {
+        let message = "Subject\n\nBody: not a trailer\n\n\
+                       Co-authored-by: Jane Doe\n  <jane@example.com>\n\
+                       tested-by : Bob <bob@example.com>\n\
+                       # a comment\n\n";
+        assert_eq!(
+            keys(message, &[]),
+            [
+                ("Co-authored-by", "Jane Doe <jane@example.com>".into()),
+                ("tested-by", "Bob <bob@example.com>".into()),
+            ]
+        );
+        // The subject is never a trailer.
+        assert_eq!(keys("Fixes: #1\n", &[]), []);
+        assert_eq!(keys("\nFixes: #1\n", &[]), []);
+    }
+
+    #[test]
+    fn mixed_paragraphs() {
+        let message = "Subject\n\nSee the\ndocs for\nthe details.\n\
+                       Signed-off-by: Jane <jane@example.com>\n";
+        // A quarter of the lines are trailers, one with a key git adds.
+        assert_eq!(
+            keys(message, &[]),
+            [("Signed-off-by", "Jane <jane@example.com>".into())]
+        );
+        let message = "Subject\n\nSee the\ndocs for\nthe details.\n\
+                       Tested-by: Jane <jane@example.com>\n";
+        assert_eq!(keys(message, &[]), []);
+        assert_eq!(keys(message, &["Tested-by"]).len(), 1);
+        // Too few trailers, even with a known key.
+        let message = "Subject\n\nThis\nis\na\nlong\nparagraph.\n\
+                       Signed-off-by: Jane <jane@example.com>\n";
+        assert_eq!(keys(message, &[]), []);
+    }
+
+    #[test]
+    fn people() {
+        assert_eq!(
+            person("Jane Doe <jane@example.com>"),
+            Some(Author::new("Jane Doe".into(), "jane@example.com".into()))
+        );
+        assert_eq!(person("<jane@example.com>"), None);
+        assert_eq!(person("alice, bob"), None);
+    }
+}